@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::input::read_input;
+
+/// Arguments every binary accepts; add to a binary's own arguments with
+/// `#[command(flatten)]`
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct CommonArgs {
+    /// Read the puzzle input from this file instead of stdin
+    #[arg(long, value_name = "PATH")]
+    pub input: Option<PathBuf>,
+    /// Print how long solving took (not counting reading the input) to stderr
+    #[arg(long)]
+    pub time: bool,
+}
+
+impl CommonArgs {
+    /// The puzzle input, from `--input` if it was given and stdin otherwise
+    pub fn read_input(&self) -> anyhow::Result<String> {
+        self.read_input_with_timeout(None)
+    }
+
+    /// Like `read_input`, but give up on an idle terminal stdin after `stdin_timeout`
+    pub fn read_input_with_timeout(
+        &self,
+        stdin_timeout: Option<Duration>,
+    ) -> anyhow::Result<String> {
+        read_input(self.input.as_deref(), stdin_timeout)
+    }
+
+    /// Start timing the solve; if `--time` was given, the time is printed when the returned
+    /// timer is dropped
+    pub fn start_timer(&self) -> SolveTimer {
+        SolveTimer {
+            start: self.time.then(Instant::now),
+        }
+    }
+}
+
+/// Prints `solved in <duration>` to stderr when dropped, if it was started by `--time`
+#[derive(Debug)]
+pub struct SolveTimer {
+    start: Option<Instant>,
+}
+
+impl Drop for SolveTimer {
+    fn drop(&mut self) {
+        if let Some(start) = self.start {
+            eprintln!("solved in {:?}", start.elapsed());
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::io::BufRead;
+
+pub type ElfId = u32;
+
+/// Keeps the `n` largest calorie totals seen so far
+#[derive(Debug)]
+pub struct Best {
+    n: usize,
+    inner: BinaryHeap<Reverse<(u64, ElfId)>>,
+}
+
+impl Best {
+    pub fn new(n: usize) -> Self {
+        Best {
+            n,
+            inner: BinaryHeap::with_capacity(n + 1),
+        }
+    }
+
+    pub fn handle(&mut self, elf_id: ElfId, calories: u64) {
+        self.inner.push(Reverse((calories, elf_id)));
+        if self.inner.len() > self.n {
+            self.inner.pop();
+        }
+    }
+
+    /// The retained elves, biggest first
+    pub fn ranked(&self) -> Vec<(ElfId, u64)> {
+        let mut ranked = self
+            .inner
+            .iter()
+            .map(|Reverse((v, elf_id))| (*elf_id, *v))
+            .collect::<Vec<_>>();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        ranked
+    }
+
+    pub fn total(&self) -> u64 {
+        self.inner.iter().map(|Reverse((v, _))| *v).sum()
+    }
+}
+
+/// Total up each elf's calories, keeping the `n` biggest
+pub fn read_best<R: BufRead>(handle: R, n: usize) -> anyhow::Result<Best> {
+    let mut best = Best::new(n);
+    let mut acc = 0u64;
+    let mut current = 1u32;
+
+    for (line_number, line) in handle.lines().enumerate() {
+        let line = line?;
+        let val = line.trim();
+        if val.is_empty() {
+            best.handle(current, acc);
+            acc = 0;
+            current += 1;
+        } else {
+            let calories = val.parse::<u64>().map_err(|e| {
+                anyhow::anyhow!(
+                    "line {}: invalid calories {:?}: {}",
+                    line_number + 1,
+                    val,
+                    e
+                )
+            })?;
+            acc += calories;
+        }
+    }
+    best.handle(current, acc);
+    Ok(best)
+}
+
+/// The most calories carried by any one elf
+pub fn solve_part1(input: &str) -> anyhow::Result<String> {
+    Ok(read_best(input.as_bytes(), 1)?.total().to_string())
+}
+
+/// The calories carried by the three best-stocked elves
+pub fn solve_part2(input: &str) -> anyhow::Result<String> {
+    Ok(read_best(input.as_bytes(), 3)?.total().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read_best;
+
+    const SAMPLE: &str = "1000
+2000
+3000
+
+4000
+
+5000
+6000
+
+7000
+8000
+9000
+
+10000
+";
+
+    #[test]
+    fn test_top_one() {
+        assert_eq!(read_best(SAMPLE.as_bytes(), 1).unwrap().total(), 24000);
+    }
+
+    #[test]
+    fn test_top_three() {
+        assert_eq!(read_best(SAMPLE.as_bytes(), 3).unwrap().total(), 45000);
+    }
+
+    #[test]
+    fn test_top_more_than_elves() {
+        let best = read_best(SAMPLE.as_bytes(), 10).unwrap();
+        assert_eq!(best.inner.len(), 5);
+        assert_eq!(best.total(), 55000);
+    }
+
+    #[test]
+    fn test_top_elf() {
+        let best = read_best(SAMPLE.as_bytes(), 3).unwrap();
+        assert_eq!(best.ranked(), vec![(4, 24000), (3, 11000), (5, 10000)]);
+        assert_eq!(read_best(SAMPLE.as_bytes(), 1).unwrap().ranked()[0].0, 4);
+    }
+
+    #[test]
+    fn test_invalid_line() {
+        let err = read_best("100\n\n12x\n".as_bytes(), 3).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 3: invalid calories \"12x\": invalid digit found in string"
+        );
+    }
+}
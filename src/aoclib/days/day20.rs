@@ -0,0 +1,264 @@
+#[derive(clap::ValueEnum, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Mode {
+    Part1,
+    Part2,
+}
+
+/// Mix the input, returning the values in their final order.
+///
+/// Rather than shuffling the values themselves, we shuffle their original indexes (which
+/// keeps duplicate values distinct) and move each one to its destination with a single
+/// rotation of the slice between its old and new positions. A reverse map from original
+/// index to position is patched up over the same slice, so finding each value is O(1).
+///
+/// Returns an error if applying the key overflows any value.
+pub fn mix(input: &[i64], decryption_key: i64, rounds: usize) -> anyhow::Result<Vec<i64>> {
+    mix_with(input, decryption_key, rounds, |_, _| {})
+}
+
+/// Like [`mix`], but calls `on_round` with the round number (starting at 1) and the ring
+/// after each round
+pub fn mix_with<F: FnMut(usize, &[i64])>(
+    input: &[i64],
+    decryption_key: i64,
+    rounds: usize,
+    mut on_round: F,
+) -> anyhow::Result<Vec<i64>> {
+    let values = input
+        .iter()
+        .map(|v| {
+            v.checked_mul(decryption_key).ok_or_else(|| {
+                anyhow::anyhow!("{} times key {} is out of range", v, decryption_key)
+            })
+        })
+        .collect::<anyhow::Result<Vec<i64>>>()?;
+    let mut order = (0..values.len()).collect::<Vec<usize>>();
+    let mut positions = order.clone();
+    let len = values.len();
+    for round in 0..rounds {
+        if len < 2 {
+            // there's nowhere for anything to move to
+            on_round(round + 1, &values);
+            continue;
+        }
+        log::debug!("{:?}", values);
+        for (original_index, v) in values.iter().enumerate() {
+            let position = positions[original_index];
+            log::debug!("examining original index {}, value {}", original_index, v);
+            let new_position = (position as i64 + v.rem_euclid(len as i64 - 1))
+                .rem_euclid(len as i64 - 1) as usize;
+            let moved = if new_position > position {
+                order[position..=new_position].rotate_left(1);
+                position..=new_position
+            } else {
+                order[new_position..=position].rotate_right(1);
+                new_position..=position
+            };
+            let start = *moved.start();
+            for (offset, index) in order[moved].iter().enumerate() {
+                positions[*index] = start + offset;
+            }
+            log::debug!("{} -> {} (by {})", position, new_position, v);
+        }
+        let ring = order.iter().map(|i| values[*i]).collect::<Vec<i64>>();
+        on_round(round + 1, &ring);
+    }
+    Ok(order.into_iter().map(|i| values[i]).collect())
+}
+
+pub const DECRYPTION_KEY: i64 = 811589153;
+
+/// Sum the values 1000, 2000, and 3000 positions after the 0
+pub fn grove_coordinate_sum(mixed: &[i64]) -> anyhow::Result<i64> {
+    let zero_index = mixed
+        .iter()
+        .position(|i| *i == 0)
+        .ok_or_else(|| anyhow::anyhow!("no 0 in input"))?;
+    Ok([1000, 2000, 3000]
+        .into_iter()
+        .map(|i| {
+            let index = (zero_index + i) % mixed.len();
+            mixed[index]
+        })
+        .sum())
+}
+
+/// Decrypt the file, defaulting the key and number of rounds to whatever `mode` calls for
+pub fn solve(
+    input: &str,
+    mode: Mode,
+    key: Option<i64>,
+    rounds: Option<usize>,
+) -> anyhow::Result<i64> {
+    solve_with(input, mode, key, rounds, |_, _| {})
+}
+
+/// Like [`solve`], but calls `on_round` with the ring after each round, as [`mix_with`] does
+pub fn solve_with<F: FnMut(usize, &[i64])>(
+    input: &str,
+    mode: Mode,
+    key: Option<i64>,
+    rounds: Option<usize>,
+    on_round: F,
+) -> anyhow::Result<i64> {
+    let numbers = input
+        .lines()
+        .map(|l| l.parse::<i64>())
+        .collect::<Result<Vec<_>, _>>()?;
+    let (default_key, default_rounds) = match mode {
+        Mode::Part1 => (1, 1),
+        Mode::Part2 => (DECRYPTION_KEY, 10),
+    };
+    let (key, rounds) = (key.unwrap_or(default_key), rounds.unwrap_or(default_rounds));
+    let result = mix_with(&numbers, key, rounds, on_round)?;
+    grove_coordinate_sum(&result)
+}
+
+/// The grove coordinates after mixing once
+pub fn solve_part1(input: &str) -> anyhow::Result<String> {
+    Ok(solve(input, Mode::Part1, None, None)?.to_string())
+}
+
+/// The grove coordinates after applying the decryption key and mixing ten times
+pub fn solve_part2(input: &str) -> anyhow::Result<String> {
+    Ok(solve(input, Mode::Part2, None, None)?.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{grove_coordinate_sum, mix, mix_with, solve, Mode, DECRYPTION_KEY};
+
+    const SAMPLE: [i64; 7] = [1, 2, -3, 3, -2, 0, 4];
+
+    #[test]
+    fn test_solve_sample() {
+        let input = "1\n2\n-3\n3\n-2\n0\n4\n";
+        assert_eq!(solve(input, Mode::Part1, None, None).unwrap(), 3);
+        assert_eq!(solve(input, Mode::Part2, None, None).unwrap(), 1623178306);
+        assert_eq!(solve(input, Mode::Part2, Some(1), Some(1)).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_part1_sample() {
+        let mixed = mix(&SAMPLE, 1, 1).unwrap();
+        assert_eq!(grove_coordinate_sum(&mixed).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_part2_sample() {
+        let mixed = mix(&SAMPLE, DECRYPTION_KEY, 10).unwrap();
+        assert_eq!(grove_coordinate_sum(&mixed).unwrap(), 1623178306);
+    }
+
+    #[test]
+    fn test_large_values() {
+        let mixed = mix(&SAMPLE, DECRYPTION_KEY, 1).unwrap();
+        let zero_index = mixed.iter().position(|i| *i == 0).unwrap();
+        let mut from_zero = mixed.clone();
+        from_zero.rotate_left(zero_index);
+        assert_eq!(
+            from_zero,
+            vec![
+                0,
+                -2434767459,
+                3246356612,
+                -1623178306,
+                2434767459,
+                1623178306,
+                811589153
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicates_move_once_each() {
+        // worked by hand with the ring of four moving by value mod 3: the first 3 lands
+        // back where it started, the 1 steps past the 0, and the second 3 wraps round to
+        // the front. Mixing by value rather than by original index would move the first 3
+        // twice and leave [3, 0, 1, 3].
+        assert_eq!(mix(&[3, 1, 0, 3], 1, 1).unwrap(), vec![3, 3, 0, 1]);
+        assert_eq!(mix(&[0, 0, 0], 1, 1).unwrap(), vec![0, 0, 0]);
+    }
+
+    #[test]
+    fn test_zero_rounds() {
+        assert_eq!(mix(&SAMPLE, 1, 0).unwrap(), SAMPLE.to_vec());
+        assert_eq!(
+            mix(&SAMPLE, 2, 0).unwrap(),
+            SAMPLE.iter().map(|v| v * 2).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_intermediate_rounds() {
+        let mut rounds = vec![];
+        let mixed = mix_with(&SAMPLE, DECRYPTION_KEY, 2, |round, ring| {
+            let zero_index = ring.iter().position(|i| *i == 0).unwrap();
+            let mut from_zero = ring.to_vec();
+            from_zero.rotate_left(zero_index);
+            rounds.push((round, from_zero));
+        })
+        .unwrap();
+        assert_eq!(mixed, mix(&SAMPLE, DECRYPTION_KEY, 2).unwrap());
+        assert_eq!(
+            rounds,
+            vec![
+                (
+                    1,
+                    vec![
+                        0,
+                        -2434767459,
+                        3246356612,
+                        -1623178306,
+                        2434767459,
+                        1623178306,
+                        811589153
+                    ]
+                ),
+                (
+                    2,
+                    vec![
+                        0,
+                        2434767459,
+                        1623178306,
+                        3246356612,
+                        -2434767459,
+                        -1623178306,
+                        811589153
+                    ]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_zero() {
+        for input in ["", "1\n2\n-3\n"] {
+            let err = solve(input, Mode::Part1, None, None).unwrap_err();
+            assert_eq!(err.to_string(), "no 0 in input");
+        }
+    }
+
+    #[test]
+    fn test_key_overflow() {
+        let err = mix(&SAMPLE, i64::MAX, 1).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("2 times key {} is out of range", i64::MAX)
+        );
+        assert!(solve("0\n-2\n", Mode::Part2, Some(i64::MIN), None).is_err());
+        // huge values that do fit still move by their remainder
+        assert_eq!(mix(&[i64::MAX, 0, 1], 1, 1).unwrap(), vec![0, 1, i64::MAX]);
+    }
+
+    #[test]
+    fn test_single_value_rounds() {
+        let mut rounds = vec![];
+        let mixed = mix_with(&[0], 3, 2, |round, ring| {
+            rounds.push((round, ring.to_vec()))
+        })
+        .unwrap();
+        assert_eq!(mixed, vec![0]);
+        assert_eq!(rounds, vec![(1, vec![0]), (2, vec![0])]);
+    }
+}
@@ -0,0 +1,214 @@
+/// Digits for -1, -2, and -3 in the balanced bases; the non-negative digits are `0`, `1`, ...
+const NEGATIVE_DIGITS: [char; 3] = ['-', '=', '~'];
+
+/// The largest digit in balanced base `base`, which runs from `-max` to `max`
+fn max_digit(base: u32) -> anyhow::Result<i64> {
+    if !matches!(base, 3 | 5 | 7) {
+        anyhow::bail!(
+            "balanced base {} isn't supported (only 3, 5, and 7 are)",
+            base
+        );
+    }
+    Ok(base as i64 / 2)
+}
+
+fn digit_char(digit: i64) -> char {
+    if digit >= 0 {
+        char::from_digit(digit as u32, 10).unwrap()
+    } else {
+        NEGATIVE_DIGITS[(-digit - 1) as usize]
+    }
+}
+
+fn digit_value(chr: char, max: i64) -> Option<i64> {
+    let value = match chr.to_digit(10) {
+        Some(d) => d as i64,
+        None => -(NEGATIVE_DIGITS.iter().position(|c| *c == chr)? as i64 + 1),
+    };
+    (value.abs() <= max).then_some(value)
+}
+
+/// Read a number written in balanced base `base` (3, 5, or 7), where digits run from
+/// `-base / 2` to `base / 2`. From lowest to highest, the digits are:
+///
+/// - base 3: `-`, `0`, `1`
+/// - base 5: `=`, `-`, `0`, `1`, `2` (SNAFU)
+/// - base 7: `~`, `=`, `-`, `0`, `1`, `2`, `3`
+pub fn from_balanced(s: &str, base: u32) -> anyhow::Result<i64> {
+    let max = max_digit(base)?;
+    if s.is_empty() {
+        anyhow::bail!("empty balanced base-{} number", base);
+    }
+    let out_of_range = || anyhow::anyhow!("balanced base-{} number {:?} is out of range", base, s);
+    // the last digit can take the value back into range after the rest overshoots it (as it
+    // does for i64::MIN), so accumulate in a wider type
+    let value = s.chars().try_fold(0i128, |val, chr| {
+        let digit = digit_value(chr, max).ok_or_else(|| {
+            anyhow::anyhow!("invalid balanced base-{} digit {:?} in {:?}", base, chr, s)
+        })?;
+        val.checked_mul(base as i128)
+            .and_then(|v| v.checked_add(digit as i128))
+            .ok_or_else(out_of_range)
+    })?;
+    i64::try_from(value).map_err(|_| out_of_range())
+}
+
+/// Write `i` in balanced base `base` (3, 5, or 7), using the digits listed on
+/// `from_balanced`. Any other base is an error.
+pub fn to_balanced(i: i64, base: u32) -> anyhow::Result<String> {
+    let max = max_digit(base)? as i128;
+    let base = base as i128;
+    if i == 0 {
+        return Ok("0".to_string());
+    }
+    // taking the digit off can step just past the ends of i64, so work in a wider type
+    let mut i = i as i128;
+    let mut c = vec![];
+    // rem_euclid keeps the remainder in 0..base for negative numbers too, and the division
+    // is exact once the digit's value has been taken off
+    while i != 0 {
+        let remainder = i.rem_euclid(base);
+        let digit = if remainder > max {
+            remainder - base
+        } else {
+            remainder
+        };
+        c.push(digit_char(digit as i64));
+        i = (i - digit) / base;
+    }
+    c.reverse();
+    Ok(c.into_iter().collect())
+}
+
+/// Read a SNAFU number: balanced base 5, with digits running from `=` (-2) to `2`
+pub fn parse_snafu(s: &str) -> anyhow::Result<i64> {
+    from_balanced(s, 5)
+}
+
+/// Write `i` as a SNAFU number
+pub fn to_snafu(i: i64) -> anyhow::Result<String> {
+    to_balanced(i, 5)
+}
+
+/// The sum of the fuel requirements, as a SNAFU number
+pub fn solve_part1(input: &str) -> anyhow::Result<String> {
+    let values = input
+        .lines()
+        .filter_map(|s| {
+            let s = s.trim();
+            if s.is_empty() {
+                None
+            } else {
+                Some(parse_snafu(s))
+            }
+        })
+        .collect::<anyhow::Result<Vec<i64>>>()?;
+    log::debug!("{:?}", values);
+    let sum = values
+        .iter()
+        .try_fold(0i64, |a, v| a.checked_add(*v))
+        .ok_or_else(|| anyhow::anyhow!("fuel requirements overflowed"))?;
+    log::debug!("sum: {}", sum);
+    to_snafu(sum)
+}
+
+/// Day 25 only has the one puzzle
+pub fn solve_part2(_input: &str) -> anyhow::Result<String> {
+    anyhow::bail!("day 25 has no part 2")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_balanced, parse_snafu, solve_part1, to_balanced, to_snafu};
+
+    #[test]
+    fn test_to_snafu() {
+        for (n, s) in [
+            (1, "1"),
+            (3, "1="),
+            (8, "2="),
+            (20, "1-0"),
+            (2022, "1=11-2"),
+            (314159265, "1121-1110-1=0"),
+        ] {
+            assert_eq!(to_snafu(n).unwrap(), s);
+        }
+    }
+
+    #[test]
+    fn test_to_snafu_zero_and_negative() {
+        assert_eq!(to_snafu(0).unwrap(), "0");
+        assert_eq!(to_snafu(-1).unwrap(), "-");
+        assert_eq!(to_snafu(-2).unwrap(), "=");
+        assert_eq!(to_snafu(-3).unwrap(), "-2");
+        assert_eq!(to_snafu(-2022).unwrap(), "-2--1=");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for n in -1000..=1000 {
+            assert_eq!(parse_snafu(&to_snafu(n).unwrap()).unwrap(), n, "{}", n);
+        }
+    }
+
+    #[test]
+    fn test_balanced_ternary() {
+        assert_eq!(to_balanced(8, 3).unwrap(), "10-");
+        assert_eq!(to_balanced(-8, 3).unwrap(), "-01");
+        assert_eq!(to_balanced(5, 3).unwrap(), "1--");
+        assert_eq!(to_balanced(0, 3).unwrap(), "0");
+        assert_eq!(from_balanced("10-", 3).unwrap(), 8);
+        assert!(from_balanced("12", 3).is_err());
+        assert!(from_balanced("1=", 3).is_err());
+    }
+
+    #[test]
+    fn test_balanced_septenary() {
+        assert_eq!(to_balanced(10, 7).unwrap(), "13");
+        assert_eq!(to_balanced(11, 7).unwrap(), "2~");
+        assert_eq!(to_balanced(-22, 7).unwrap(), "~-");
+        assert_eq!(to_balanced(-24, 7).unwrap(), "~~");
+        assert_eq!(from_balanced("2~", 7).unwrap(), 11);
+        assert!(from_balanced("2≡", 7).is_err());
+        assert!(from_balanced("4", 7).is_err());
+    }
+
+    #[test]
+    fn test_balanced_round_trip() {
+        for base in [3, 5, 7] {
+            for n in -1000..=1000 {
+                assert_eq!(
+                    from_balanced(&to_balanced(n, base).unwrap(), base).unwrap(),
+                    n
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_unsupported_base() {
+        for base in [0, 1, 2, 4, 9] {
+            assert!(to_balanced(1, base).is_err(), "{}", base);
+            assert!(from_balanced("1", base).is_err(), "{}", base);
+        }
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        for n in [i64::MIN, i64::MAX] {
+            assert_eq!(parse_snafu(&to_snafu(n).unwrap()).unwrap(), n);
+        }
+        assert!(parse_snafu(&"2".repeat(30)).is_err());
+        assert!(parse_snafu(&"2".repeat(100)).is_err());
+        assert!(from_balanced(&"~".repeat(30), 7).is_err());
+        assert!(solve_part1(&format!("{}\n1\n", to_snafu(i64::MAX).unwrap())).is_err());
+    }
+
+    #[test]
+    fn test_snafu_sample() {
+        let sample = "1=-0-2\n12111\n2=0=\n21\n2=01\n111\n20012\n112\n1=-1=\n1-12\n12\n1=\n122\n";
+        assert_eq!(solve_part1(sample).unwrap(), "2=-1=0");
+        assert!(parse_snafu("1=3").is_err());
+        assert!(parse_snafu("").is_err());
+    }
+}
@@ -0,0 +1,189 @@
+use std::collections::VecDeque;
+use std::io::BufRead;
+
+/// A fixed-size window over the last `N` values pushed
+struct RingyBuf<T: Default + Copy, const N: usize> {
+    storage: [T; N],
+    full: bool,
+    i: usize,
+}
+
+impl<T: Default + Copy, const N: usize> RingyBuf<T, N> {
+    fn new() -> Self {
+        Self {
+            storage: [T::default(); N],
+            full: false,
+            i: 0,
+        }
+    }
+
+    /// Add a value, returning the one it pushed out of the window, if any
+    fn push(&mut self, value: T) -> Option<T> {
+        let evicted = self.full.then_some(self.storage[self.i]);
+        self.storage[self.i] = value;
+        self.i = (self.i + 1) % N;
+        if self.i == 0 {
+            self.full = true;
+        }
+        evicted
+    }
+
+    fn is_full(&self) -> bool {
+        self.full
+    }
+}
+
+/// Find the end of the first window of `N` distinct bytes. Keeps a count of each byte in the
+/// window and of how many different bytes there are, so every step is O(1).
+pub fn run<const N: usize, R: BufRead>(io: R) -> Option<usize> {
+    let mut r = RingyBuf::<u8, N>::new();
+    let mut counts = [0usize; 256];
+    let mut distinct = 0;
+    for (i, b) in io.bytes().map_while(Result::ok).enumerate() {
+        if let Some(old) = r.push(b) {
+            counts[old as usize] -= 1;
+            if counts[old as usize] == 0 {
+                distinct -= 1;
+            }
+        }
+        counts[b as usize] += 1;
+        if counts[b as usize] == 1 {
+            distinct += 1;
+        }
+        if r.is_full() && distinct == N {
+            return Some(i + 1);
+        }
+    }
+    None
+}
+
+/// Like `run`, but with the window size picked at runtime. Keeps a count of each byte in
+/// the window and of how many bytes appear more than once, so every step is O(1).
+///
+/// Returns an error if `window` is 0.
+pub fn run_window<R: BufRead>(io: R, window: usize) -> anyhow::Result<Option<usize>> {
+    if window == 0 {
+        anyhow::bail!("window must be at least 1 byte");
+    }
+    let mut buffer = VecDeque::with_capacity(window);
+    let mut counts = [0usize; 256];
+    let mut repeated = 0;
+    for (i, b) in io.bytes().map_while(Result::ok).enumerate() {
+        if buffer.len() == window {
+            let old = buffer.pop_front().unwrap();
+            counts[old as usize] -= 1;
+            if counts[old as usize] == 1 {
+                repeated -= 1;
+            }
+        }
+        buffer.push_back(b);
+        counts[b as usize] += 1;
+        if counts[b as usize] == 2 {
+            repeated += 1;
+        }
+        if buffer.len() == window && repeated == 0 {
+            return Ok(Some(i + 1));
+        }
+    }
+    Ok(None)
+}
+
+/// Where the first start-of-packet marker (four distinct characters) ends
+pub fn solve_part1(input: &str) -> anyhow::Result<String> {
+    run::<4, _>(input.as_bytes())
+        .map(|found| found.to_string())
+        .ok_or_else(|| anyhow::anyhow!("no start-of-packet marker found"))
+}
+
+/// Where the first start-of-message marker (fourteen distinct characters) ends
+pub fn solve_part2(input: &str) -> anyhow::Result<String> {
+    run::<14, _>(input.as_bytes())
+        .map(|found| found.to_string())
+        .ok_or_else(|| anyhow::anyhow!("no start-of-message marker found"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run, run_window};
+    use std::collections::HashSet;
+
+    /// Check every window from scratch
+    fn brute_force(input: &[u8], window: usize) -> Option<usize> {
+        (window..=input.len()).find(|end| {
+            input[end - window..*end]
+                .iter()
+                .collect::<HashSet<_>>()
+                .len()
+                == window
+        })
+    }
+
+    /// The puzzle's examples, with their start-of-packet and start-of-message positions
+    const EXAMPLES: [(&str, usize, usize); 5] = [
+        ("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 7, 19),
+        ("bvwbjplbgvbhsrlpgdmjqwftvncz", 5, 23),
+        ("nppdvjthqldpwncqszvftbrmjlhg", 6, 23),
+        ("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 10, 29),
+        ("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11, 26),
+    ];
+
+    #[test]
+    fn test_examples() {
+        for (input, packet, message) in EXAMPLES {
+            let input = input.as_bytes();
+            assert_eq!(run::<4, _>(input), Some(packet));
+            assert_eq!(run::<14, _>(input), Some(message));
+            assert_eq!(run_window(input, 4).unwrap(), Some(packet));
+            assert_eq!(run_window(input, 14).unwrap(), Some(message));
+        }
+    }
+
+    #[test]
+    fn test_window_sizes() {
+        for (window, expected) in [(3, [3, 3]), (7, [10, 15])] {
+            for ((sample, _, _), expected) in EXAMPLES.iter().zip(expected) {
+                assert_eq!(
+                    run_window(sample.as_bytes(), window).unwrap(),
+                    Some(expected)
+                );
+            }
+        }
+        assert_eq!(run_window("aaaa".as_bytes(), 1).unwrap(), Some(1));
+        assert_eq!(run_window("aaaa".as_bytes(), 2).unwrap(), None);
+        assert_eq!(run_window("ab".as_bytes(), 3).unwrap(), None);
+    }
+
+    #[test]
+    fn test_zero_window() {
+        assert!(run_window(EXAMPLES[0].0.as_bytes(), 0).is_err());
+        assert!(run_window("".as_bytes(), 0).is_err());
+    }
+
+    #[test]
+    fn test_edge_cases() {
+        assert_eq!(run::<4, _>("".as_bytes()), None);
+        assert_eq!(run::<4, _>("abc".as_bytes()), None);
+        assert_eq!(run::<4, _>("abcd".as_bytes()), Some(4));
+        assert_eq!(run::<4, _>("aaaa".as_bytes()), None);
+        // a repeat that drops out of the window just as the last new byte arrives
+        assert_eq!(run::<4, _>("abcadef".as_bytes()), Some(5));
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        // a long input with only a few distinct letters until the very end
+        let mut long = "abcabcabd".repeat(10000);
+        long.push_str("efghijklmnopqrstuvwxyz");
+        // "cabdefghijklmn" starts 5 bytes into the last repeat
+        assert_eq!(run::<14, _>(long.as_bytes()), Some(90000 - 4 + 14));
+        let inputs = EXAMPLES
+            .iter()
+            .map(|(input, _, _)| input.to_string())
+            .chain([long, "aaaa".to_string()]);
+        for input in inputs {
+            let input = input.as_bytes();
+            assert_eq!(run::<4, _>(input), brute_force(input, 4));
+            assert_eq!(run::<14, _>(input), brute_force(input, 14));
+        }
+    }
+}
@@ -0,0 +1,31 @@
+//! Solvers for the days whose binaries are thin wrappers, so they can be called (and tested)
+//! without going through stdin
+//!
+//! Every module has `solve_part1` and `solve_part2`, which take the puzzle input and return
+//! the answer as it would be printed.
+
+pub mod day1;
+pub mod day20;
+pub mod day25;
+pub mod day6;
+
+/// A day's `solve_part1` or `solve_part2`
+pub type Solver = fn(&str) -> anyhow::Result<String>;
+
+/// Every day that has a library solver, with its parts 1 and 2
+pub const SOLVERS: [(u32, Solver, Solver); 4] = [
+    (1, day1::solve_part1, day1::solve_part2),
+    (6, day6::solve_part1, day6::solve_part2),
+    (20, day20::solve_part1, day20::solve_part2),
+    (25, day25::solve_part1, day25::solve_part2),
+];
+
+/// The solver for `part` (1 or 2) of `day`, if that day has been moved into the library
+pub fn solver(day: u32, part: u32) -> Option<Solver> {
+    let (_, part1, part2) = SOLVERS.iter().find(|(d, _, _)| *d == day)?;
+    match part {
+        1 => Some(*part1),
+        2 => Some(*part2),
+        _ => None,
+    }
+}
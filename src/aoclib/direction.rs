@@ -0,0 +1,52 @@
+use crate::Point;
+
+/// One of the four cardinal directions on a grid where y increases going down
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    North,
+    South,
+    West,
+    East,
+}
+
+impl Direction {
+    pub const fn all() -> [Direction; 4] {
+        [
+            Direction::North,
+            Direction::South,
+            Direction::West,
+            Direction::East,
+        ]
+    }
+
+    /// The offset from a point to its neighbor in this direction
+    pub const fn offset(self) -> Point {
+        match self {
+            Direction::North => Point::new(0, -1),
+            Direction::South => Point::new(0, 1),
+            Direction::West => Point::new(-1, 0),
+            Direction::East => Point::new(1, 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Direction;
+    use crate::Point;
+
+    #[test]
+    fn test_all_contains_each_direction_once() {
+        let all = Direction::all();
+        for direction in [
+            Direction::North,
+            Direction::South,
+            Direction::West,
+            Direction::East,
+        ] {
+            assert_eq!(all.iter().filter(|d| **d == direction).count(), 1);
+        }
+        let total = all.iter().fold(Point::new(0, 0), |acc, d| acc + d.offset());
+        assert_eq!(total, Point::new(0, 0));
+    }
+}
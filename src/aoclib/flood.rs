@@ -0,0 +1,92 @@
+use std::collections::{HashSet, VecDeque};
+use std::hash::Hash;
+
+/// How a flood fill should treat a cell it reaches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fill {
+    /// Part of the region; keep filling from here
+    Passable,
+    /// Not part of the region
+    Blocked,
+    /// Not part of the region, and reaching it means the region isn't enclosed (e.g. the
+    /// edge of the grid, or a cell already known to be outside)
+    Boundary,
+}
+
+/// Breadth-first fill outward from `start`, which is assumed to be passable.
+///
+/// `neighbors` defines the topology, so this works the same on 2D or 3D grids (or anything
+/// else). Returns every cell in the connected region, and whether the fill touched a
+/// boundary.
+pub fn flood_fill<P, N, I, C>(start: P, neighbors: N, mut classify: C) -> (Vec<P>, bool)
+where
+    P: Copy + Eq + Hash,
+    N: Fn(&P) -> I,
+    I: IntoIterator<Item = P>,
+    C: FnMut(&P) -> Fill,
+{
+    let mut connected = vec![];
+    let mut todo = VecDeque::new();
+    let mut seen = HashSet::new();
+    let mut touched_boundary = false;
+    seen.insert(start);
+    todo.push_back(start);
+    while let Some(next) = todo.pop_front() {
+        connected.push(next);
+        for neighbor in neighbors(&next) {
+            match classify(&neighbor) {
+                Fill::Passable => {
+                    if seen.insert(neighbor) {
+                        todo.push_back(neighbor);
+                    }
+                }
+                Fill::Blocked => {}
+                Fill::Boundary => touched_boundary = true,
+            }
+        }
+    }
+    (connected, touched_boundary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{flood_fill, Fill};
+    use crate::{Direction, Point};
+
+    fn fill_from(map: &[&str], start: Point) -> (Vec<Point>, bool) {
+        flood_fill(
+            start,
+            |p| Direction::all().map(|d| *p + d.offset()),
+            |p| {
+                if p.x < 0 || p.y < 0 || p.y as usize >= map.len() {
+                    return Fill::Boundary;
+                }
+                match map[p.y as usize].as_bytes().get(p.x as usize) {
+                    None => Fill::Boundary,
+                    Some(b'#') => Fill::Blocked,
+                    Some(_) => Fill::Passable,
+                }
+            },
+        )
+    }
+
+    #[test]
+    fn test_pocket_and_open_region() {
+        let map = [
+            "......", //
+            ".####.", //
+            ".#..#.", //
+            ".####.", //
+            "......",
+        ];
+        let (pocket, open) = fill_from(&map, Point::new(2, 2));
+        assert!(!open);
+        assert_eq!(pocket.len(), 2);
+        assert!(pocket.contains(&Point::new(3, 2)));
+
+        let (outside, open) = fill_from(&map, Point::new(0, 0));
+        assert!(open);
+        assert_eq!(outside.len(), 30 - 10 - 2);
+        assert!(!outside.contains(&Point::new(2, 2)));
+    }
+}
@@ -9,7 +9,7 @@ pub trait HasEmpty {
     fn empty_value() -> Self;
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct DenseGrid<V: Clone + fmt::Debug> {
     min_x: Index,
     min_y: Index,
@@ -26,6 +26,27 @@ impl<V: Clone + fmt::Debug + HasEmpty> DenseGrid<V> {
     }
 }
 
+impl DenseGrid<u32> {
+    /// A grid just big enough to hold every point, counting how many times each one showed
+    /// up. With no points at all, this is a single empty cell at the origin.
+    pub fn from_points(points: impl IntoIterator<Item = Point<Index>>) -> Self {
+        let points = points.into_iter().collect::<Vec<_>>();
+        let upper_left = Point::new(
+            points.iter().map(|p| p.x).min().unwrap_or(0),
+            points.iter().map(|p| p.y).min().unwrap_or(0),
+        );
+        let lower_right = Point::new(
+            points.iter().map(|p| p.x).max().unwrap_or(0),
+            points.iter().map(|p| p.y).max().unwrap_or(0),
+        );
+        let mut grid = Self::new_with(upper_left, lower_right, 0);
+        for point in points {
+            grid[point] += 1;
+        }
+        grid
+    }
+}
+
 impl<V: Clone + fmt::Debug> DenseGrid<V> {
     pub fn new_with(upper_left: Point<Index>, lower_right: Point<Index>, empty_value: V) -> Self {
         let min_x = min(upper_left.x, lower_right.x);
@@ -45,6 +66,14 @@ impl<V: Clone + fmt::Debug> DenseGrid<V> {
         }
     }
 
+    /// A `size` × `size` grid with its upper-left corner at the origin. Panics if `size` is 0,
+    /// since a grid always has at least one cell.
+    pub fn new_square(size: usize, empty_value: V) -> Self {
+        assert!(size > 0, "grids must have at least one cell");
+        let far = size as Index - 1;
+        Self::new_with(Point::new(0, 0), Point::new(far, far), empty_value)
+    }
+
     pub fn width(&self) -> usize {
         self.width
     }
@@ -57,6 +86,30 @@ impl<V: Clone + fmt::Debug> DenseGrid<V> {
         self.width * self.height
     }
 
+    /// The cells of row `y`, from left to right. Panics if the row is out-of-bounds.
+    pub fn row(&self, y: Index) -> impl DoubleEndedIterator<Item = &V> + ExactSizeIterator + Clone {
+        let start = self
+            .index_for(Point::new(self.min_x, y))
+            .unwrap_or_else(|| panic!("row {} is out of bounds", y));
+        self.cells[start..start + self.width].iter()
+    }
+
+    /// The cells of column `x`, from top to bottom. Panics if the column is out-of-bounds.
+    pub fn column(
+        &self,
+        x: Index,
+    ) -> impl DoubleEndedIterator<Item = &V> + ExactSizeIterator + Clone {
+        let start = self
+            .index_for(Point::new(x, self.min_y))
+            .unwrap_or_else(|| panic!("column {} is out of bounds", x));
+        self.cells[start..].iter().step_by(self.width)
+    }
+
+    /// Every cell in the grid, row by row, for changing in place
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.cells.iter_mut()
+    }
+
     /// Get a value by coordinate. Returns None if the coordinate is out-of-bounds.
     pub fn get(&self, coordinate: Point<Index>) -> Option<V> {
         let index = self.index_for(coordinate)?;
@@ -70,6 +123,63 @@ impl<V: Clone + fmt::Debug> DenseGrid<V> {
         Some(())
     }
 
+    /// Apply `f` to a value in place. Returns false if the coordinate is out-of-bounds.
+    pub fn update<F: FnOnce(&mut V)>(&mut self, coordinate: Point<Index>, f: F) -> bool {
+        match self.index_for(coordinate) {
+            Some(index) => {
+                f(&mut self.cells[index]);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Count how many of the 8 cells surrounding `coordinate` match `pred`. Cells outside
+    /// the grid never match.
+    pub fn count_neighbors8<F: Fn(&V) -> bool>(&self, coordinate: Point<Index>, pred: F) -> usize {
+        (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| Point::new(dx, dy)))
+            .filter(|offset| *offset != Point::new(0, 0))
+            .filter_map(|offset| self.get(coordinate + offset))
+            .filter(|v| pred(v))
+            .count()
+    }
+
+    /// Compute the next generation of a cellular automaton: every cell of the new grid is
+    /// `rule` applied to the old cell and its in-bounds 8-neighbors (with their coordinates).
+    /// The old grid is left untouched, so every cell sees the same generation.
+    pub fn apply_rule<F: Fn(&V, &[(Point<Index>, &V)]) -> V>(&self, rule: F) -> Self {
+        let mut cells = Vec::with_capacity(self.cells.len());
+        let mut neighbors = Vec::with_capacity(8);
+        for y in self.min_y..=self.max_y {
+            for x in self.min_x..=self.max_x {
+                let coordinate = Point::new(x, y);
+                neighbors.clear();
+                for dy in -1..=1 {
+                    for dx in -1..=1 {
+                        let neighbor = coordinate + Point::new(dx, dy);
+                        if neighbor == coordinate {
+                            continue;
+                        }
+                        if let Some(index) = self.index_for(neighbor) {
+                            neighbors.push((neighbor, &self.cells[index]));
+                        }
+                    }
+                }
+                cells.push(rule(&self[coordinate], &neighbors));
+            }
+        }
+        Self {
+            min_x: self.min_x,
+            min_y: self.min_y,
+            max_x: self.max_x,
+            max_y: self.max_y,
+            width: self.width,
+            height: self.height,
+            cells,
+        }
+    }
+
     pub fn contains(&self, coordinate: Point<Index>) -> bool {
         coordinate.x >= self.min_x
             && coordinate.x <= self.max_x
@@ -77,7 +187,58 @@ impl<V: Clone + fmt::Debug> DenseGrid<V> {
             && coordinate.y <= self.max_y
     }
 
+    /// The upper-left and lower-right corners of the smallest rectangle containing every cell
+    /// matching `pred`, or `None` if no cells match.
+    pub fn extent<F: Fn(&V) -> bool>(&self, pred: F) -> Option<(Point<Index>, Point<Index>)> {
+        let mut bounds: Option<(Point<Index>, Point<Index>)> = None;
+        for y in self.min_y..=self.max_y {
+            for x in self.min_x..=self.max_x {
+                let coordinate = Point::new(x, y);
+                if !pred(&self[coordinate]) {
+                    continue;
+                }
+                bounds = Some(match bounds {
+                    None => (coordinate, coordinate),
+                    Some((upper_left, lower_right)) => (
+                        Point::new(min(upper_left.x, x), min(upper_left.y, y)),
+                        Point::new(max(lower_right.x, x), max(lower_right.y, y)),
+                    ),
+                });
+            }
+        }
+        bounds
+    }
+
+    /// Return a new grid trimmed to the smallest rectangle containing every cell matching
+    /// `pred`. If no cells match, a copy of the original grid is returned.
+    pub fn crop_to<F: Fn(&V) -> bool>(&self, pred: F) -> Self {
+        let Some((upper_left, lower_right)) = self.extent(pred) else {
+            return self.clone();
+        };
+        let mut cropped = Self::new_with(upper_left, lower_right, self[upper_left].clone());
+        for y in upper_left.y..=lower_right.y {
+            for x in upper_left.x..=lower_right.x {
+                let coordinate = Point::new(x, y);
+                cropped[coordinate] = self[coordinate].clone();
+            }
+        }
+        cropped
+    }
+
+    /// Write the grid to stdout, one row per line, using `f` to render each cell.
     pub fn dump_with<F: Fn(&V) -> char>(&self, f: F) {
+        let stdout = std::io::stdout();
+        let mut handle = stdout.lock();
+        self.dump_to(&mut handle, f)
+            .expect("failed to write grid to stdout");
+    }
+
+    /// Write the grid to `w`, one row per line, using `f` to render each cell.
+    pub fn dump_to<W: std::io::Write, F: Fn(&V) -> char>(
+        &self,
+        w: &mut W,
+        f: F,
+    ) -> std::io::Result<()> {
         for y in self.min_y..=self.max_y {
             let cells = (self.min_x..=self.max_x)
                 .map(|x| {
@@ -85,8 +246,9 @@ impl<V: Clone + fmt::Debug> DenseGrid<V> {
                     f(&self[coordinate])
                 })
                 .collect::<String>();
-            println!("{}", cells);
+            writeln!(w, "{}", cells)?;
         }
+        Ok(())
     }
 
     fn index_for(&self, coordinate: Point<Index>) -> Option<usize> {
@@ -120,9 +282,41 @@ impl<V: Clone + std::fmt::Debug> std::ops::IndexMut<Point<Index>> for DenseGrid<
     }
 }
 
+/// Parse a rectangular grid of single decimal digits, with (0, 0) at the upper-left
+pub fn parse_digit_grid(s: &str) -> anyhow::Result<DenseGrid<u8>> {
+    let lines = s
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>();
+    let width = match lines.first() {
+        Some(line) => line.len(),
+        None => anyhow::bail!("no lines found"),
+    };
+    let lower_right = Point::new(width as Index - 1, lines.len() as Index - 1);
+    let mut grid = DenseGrid::new_with(Point::new(0, 0), lower_right, 0u8);
+    for (y, line) in lines.iter().enumerate() {
+        if line.len() != width {
+            anyhow::bail!(
+                "line {} has length {}, expected {}",
+                y + 1,
+                line.len(),
+                width
+            );
+        }
+        for (x, chr) in line.chars().enumerate() {
+            let digit = chr
+                .to_digit(10)
+                .ok_or_else(|| anyhow::anyhow!("invalid digit {:?} at ({}, {})", chr, x, y))?;
+            grid[Point::new(x as Index, y as Index)] = digit as u8;
+        }
+    }
+    Ok(grid)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{DenseGrid, Point};
+    use super::{parse_digit_grid, DenseGrid, Point};
 
     #[test]
     fn test_small() {
@@ -144,4 +338,216 @@ mod tests {
         assert_eq!(g[Point { x: 49, y: 50 }], 0);
         assert_eq!(g[Point { x: 50, y: 50 }], 4);
     }
+
+    #[test]
+    fn test_new_square() {
+        let g = DenseGrid::new_square(5, 0u8);
+        assert_eq!(g.width(), 5);
+        assert_eq!(g.height(), 5);
+        assert_eq!(g.size(), 25);
+        assert!(g.contains(Point::new(0, 0)));
+        assert!(g.contains(Point::new(4, 4)));
+        assert!(!g.contains(Point::new(5, 0)));
+        assert!(!g.contains(Point::new(-1, 0)));
+        assert_eq!(DenseGrid::new_square(1, 'x').size(), 1);
+    }
+
+    #[test]
+    fn test_update() {
+        let mut g = DenseGrid::new_with(Point { x: 0, y: 0 }, Point { x: 2, y: 2 }, 0u32);
+        for _ in 0..3 {
+            assert!(g.update(Point { x: 1, y: 2 }, |v| *v += 1));
+        }
+        assert!(!g.update(Point { x: 3, y: 2 }, |v| *v += 1));
+        assert_eq!(g[Point { x: 1, y: 2 }], 3);
+        assert_eq!(g[Point { x: 0, y: 0 }], 0);
+    }
+
+    #[test]
+    fn test_count_neighbors8() {
+        let mut g = DenseGrid::new_with(Point { x: 0, y: 0 }, Point { x: 2, y: 2 }, false);
+        g[Point { x: 0, y: 0 }] = true;
+        g[Point { x: 1, y: 0 }] = true;
+        g[Point { x: 2, y: 2 }] = true;
+        g[Point { x: 1, y: 1 }] = true;
+        assert_eq!(g.count_neighbors8(Point { x: 1, y: 1 }, |v| *v), 3);
+        assert_eq!(g.count_neighbors8(Point { x: 1, y: 1 }, |v| !*v), 5);
+        assert_eq!(g.count_neighbors8(Point { x: 0, y: 0 }, |v| *v), 2);
+        assert_eq!(g.count_neighbors8(Point { x: 0, y: 0 }, |v| !*v), 1);
+    }
+
+    #[test]
+    fn test_dump_to() {
+        let mut g = DenseGrid::new_with(Point { x: 0, y: 0 }, Point { x: 2, y: 1 }, false);
+        g[Point { x: 1, y: 0 }] = true;
+        g[Point { x: 2, y: 1 }] = true;
+        let mut out = Vec::new();
+        g.dump_to(&mut out, |v| if *v { '#' } else { '.' }).unwrap();
+        assert_eq!(out, b".#.\n..#\n");
+    }
+
+    #[test]
+    fn test_crop_to_single() {
+        let mut g = DenseGrid::new_with(Point { x: 0, y: 0 }, Point { x: 9, y: 9 }, 0u8);
+        g[Point { x: 3, y: 7 }] = 1;
+        let cropped = g.crop_to(|v| *v == 1);
+        assert_eq!(cropped.width(), 1);
+        assert_eq!(cropped.height(), 1);
+        assert_eq!(cropped.get(Point { x: 3, y: 7 }), Some(1));
+        assert_eq!(cropped.get(Point { x: 0, y: 0 }), None);
+    }
+
+    #[test]
+    fn test_crop_to_scattered() {
+        let mut g = DenseGrid::new_with(Point { x: -5, y: -5 }, Point { x: 5, y: 5 }, 0u8);
+        g[Point { x: -2, y: 1 }] = 1;
+        g[Point { x: 3, y: -4 }] = 1;
+        g[Point { x: 0, y: 2 }] = 1;
+        let cropped = g.crop_to(|v| *v == 1);
+        assert_eq!(cropped.width(), 6);
+        assert_eq!(cropped.height(), 7);
+        assert!(cropped.contains(Point { x: -2, y: -4 }));
+        assert!(cropped.contains(Point { x: 3, y: 2 }));
+        assert!(!cropped.contains(Point { x: -3, y: 0 }));
+        assert!(!cropped.contains(Point { x: 0, y: 3 }));
+        assert_eq!(cropped[Point { x: 0, y: 2 }], 1);
+        assert_eq!(cropped[Point { x: 0, y: 0 }], 0);
+    }
+
+    #[test]
+    fn test_crop_to_no_match() {
+        let g = DenseGrid::new_with(Point { x: 0, y: 0 }, Point { x: 4, y: 2 }, 0u8);
+        let cropped = g.crop_to(|v| *v == 1);
+        assert_eq!(cropped.width(), 5);
+        assert_eq!(cropped.height(), 3);
+    }
+
+    #[test]
+    fn test_parse_digit_grid() {
+        let g = parse_digit_grid("123\n456\n").unwrap();
+        assert_eq!(g.width(), 3);
+        assert_eq!(g.height(), 2);
+        assert_eq!(g[Point { x: 0, y: 0 }], 1);
+        assert_eq!(g[Point { x: 2, y: 0 }], 3);
+        assert_eq!(g[Point { x: 1, y: 1 }], 5);
+    }
+
+    #[test]
+    fn test_parse_digit_grid_invalid() {
+        let err = parse_digit_grid("123\n4x6\n").unwrap_err();
+        assert!(err.to_string().contains("(1, 1)"));
+        assert!(parse_digit_grid("123\n45\n").is_err());
+        assert!(parse_digit_grid("").is_err());
+    }
+
+    #[test]
+    fn test_apply_rule_blinker() {
+        let life = |alive: &bool, neighbors: &[(Point<i64>, &bool)]| {
+            let live_neighbors = neighbors.iter().filter(|(_, v)| **v).count();
+            matches!((alive, live_neighbors), (true, 2) | (_, 3))
+        };
+        let mut g = DenseGrid::new_square(5, false);
+        for x in 1..=3 {
+            g[Point { x, y: 2 }] = true;
+        }
+        let next = g.apply_rule(life);
+        assert_eq!(next.width(), 5);
+        assert_eq!(next.height(), 5);
+        for y in 0..5 {
+            for x in 0..5 {
+                assert_eq!(next[Point { x, y }], x == 2 && (1..=3).contains(&y));
+            }
+        }
+        // the original generation is untouched, and a blinker has period 2
+        assert!(g[Point { x: 1, y: 2 }]);
+        let after = next.apply_rule(life);
+        for y in 0..5 {
+            for x in 0..5 {
+                assert_eq!(after[Point { x, y }], g[Point { x, y }]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_apply_rule_neighbors() {
+        let g = DenseGrid::new_with(Point { x: -1, y: -1 }, Point { x: 1, y: 1 }, 1u8);
+        let counts = g.apply_rule(|_, neighbors| neighbors.len() as u8);
+        assert_eq!(counts[Point { x: 0, y: 0 }], 8);
+        assert_eq!(counts[Point { x: -1, y: 0 }], 5);
+        assert_eq!(counts[Point { x: 1, y: 1 }], 3);
+    }
+
+    #[test]
+    fn test_from_points() {
+        let points = [
+            Point { x: -1, y: 2 },
+            Point { x: 3, y: 0 },
+            Point { x: -1, y: 2 },
+            Point { x: 0, y: 1 },
+            Point { x: -1, y: 2 },
+            Point { x: 3, y: 0 },
+        ];
+        let g = DenseGrid::from_points(points);
+        assert_eq!(g.width(), 5);
+        assert_eq!(g.height(), 3);
+        assert_eq!(g[Point { x: -1, y: 2 }], 3);
+        assert_eq!(g[Point { x: 3, y: 0 }], 2);
+        assert_eq!(g[Point { x: 0, y: 1 }], 1);
+        assert_eq!(g[Point { x: 0, y: 0 }], 0);
+        assert!(!g.contains(Point { x: -2, y: 2 }));
+
+        let empty = DenseGrid::from_points(std::iter::empty());
+        assert_eq!(empty.size(), 1);
+        assert_eq!(empty[Point { x: 0, y: 0 }], 0);
+    }
+
+    #[test]
+    fn test_rows_and_columns() {
+        let g = parse_digit_grid("123\n456\n").unwrap();
+        assert_eq!(g.row(0).copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(g.row(1).rev().copied().collect::<Vec<_>>(), vec![6, 5, 4]);
+        assert_eq!(g.column(0).copied().collect::<Vec<_>>(), vec![1, 4]);
+        assert_eq!(g.column(2).rev().copied().collect::<Vec<_>>(), vec![6, 3]);
+
+        let mut g = DenseGrid::new_with(Point { x: -1, y: -1 }, Point { x: 1, y: 0 }, 0u8);
+        g[Point { x: 0, y: -1 }] = 1;
+        g[Point { x: 0, y: 0 }] = 2;
+        assert_eq!(g.row(-1).copied().collect::<Vec<_>>(), vec![0, 1, 0]);
+        assert_eq!(g.column(0).copied().collect::<Vec<_>>(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_values_mut() {
+        let mut g = parse_digit_grid("123\n456\n").unwrap();
+        g.values_mut().filter(|v| **v % 2 == 0).for_each(|v| *v = 0);
+        assert_eq!(g.row(0).copied().collect::<Vec<_>>(), vec![1, 0, 3]);
+        assert_eq!(g.row(1).copied().collect::<Vec<_>>(), vec![0, 5, 0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "column 3 is out of bounds")]
+    fn test_column_out_of_bounds() {
+        parse_digit_grid("123\n456\n").unwrap().column(3).count();
+    }
+
+    #[test]
+    fn test_extent() {
+        let mut g = DenseGrid::new_with(Point::new(-5, -5), Point::new(5, 5), '.');
+        assert_eq!(g.extent(|c| *c == '#'), None);
+        g[Point::new(-2, 3)] = '#';
+        assert_eq!(
+            g.extent(|c| *c == '#'),
+            Some((Point::new(-2, 3), Point::new(-2, 3)))
+        );
+        g[Point::new(4, -1)] = '#';
+        g[Point::new(0, 0)] = '#';
+        assert_eq!(
+            g.extent(|c| *c == '#'),
+            Some((Point::new(-2, -1), Point::new(4, 3)))
+        );
+        assert_eq!(
+            g.extent(|_| true),
+            Some((Point::new(-5, -5), Point::new(5, 5)))
+        );
+    }
 }
@@ -0,0 +1,189 @@
+use std::io::{IsTerminal, Read};
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Read all of stdin, giving up after `timeout` if stdin is a terminal that never sends
+/// anything (which usually means someone forgot to pipe their input in)
+pub fn read_stdin(timeout: Option<Duration>) -> anyhow::Result<String> {
+    let stdin = std::io::stdin();
+    let is_tty = stdin.is_terminal();
+    read_with_timeout(stdin, is_tty, timeout)
+}
+
+/// Read the file at `path`, or all of stdin (see `read_stdin`) if there isn't one
+pub fn read_input(path: Option<&Path>, stdin_timeout: Option<Duration>) -> anyhow::Result<String> {
+    match path {
+        Some(path) => read_file(path),
+        None => read_stdin(stdin_timeout),
+    }
+}
+
+/// Read the file at `path`, or all of `stdin` if there isn't one
+pub fn read_input_from<R: Read>(path: Option<&Path>, mut stdin: R) -> anyhow::Result<String> {
+    match path {
+        Some(path) => read_file(path),
+        None => {
+            let mut buf = String::new();
+            stdin.read_to_string(&mut buf)?;
+            Ok(buf)
+        }
+    }
+}
+
+fn read_file(path: &Path) -> anyhow::Result<String> {
+    std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("could not read {}: {}", path.display(), e))
+}
+
+/// Read `reader` to the end. If it's interactive and a timeout is given, the read happens on
+/// a background thread and an error is returned if nothing has arrived in time; once input
+/// starts arriving there is no deadline.
+pub fn read_with_timeout<R: Read + Send + 'static>(
+    mut reader: R,
+    is_tty: bool,
+    timeout: Option<Duration>,
+) -> anyhow::Result<String> {
+    let timeout = match timeout {
+        Some(timeout) if is_tty => timeout,
+        _ => {
+            let mut buf = String::new();
+            reader.read_to_string(&mut buf)?;
+            return Ok(buf);
+        }
+    };
+    // the reader thread sends one message when the first read returns and another with the
+    // whole input; only the first is subject to the timeout, so slow typing or pasting on a
+    // terminal isn't cut off once it has started
+    let (first_tx, first_rx) = mpsc::channel();
+    let (done_tx, done_rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 4096];
+        let first = loop {
+            match reader.read(&mut chunk) {
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                res => break res,
+            }
+        };
+        // the receivers are gone if we already timed out
+        let _ = first_tx.send(());
+        let res = first.and_then(|n| {
+            buf.extend_from_slice(&chunk[..n]);
+            if n > 0 {
+                reader.read_to_end(&mut buf)?;
+            }
+            String::from_utf8(buf)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+        });
+        let _ = done_tx.send(res);
+    });
+    match first_rx.recv_timeout(timeout) {
+        Ok(()) => {}
+        Err(mpsc::RecvTimeoutError::Timeout) => anyhow::bail!(
+            "no input received on stdin after {:?}; did you forget to pipe in your puzzle input?",
+            timeout
+        ),
+        Err(mpsc::RecvTimeoutError::Disconnected) => {
+            anyhow::bail!("stdin reader exited without a result")
+        }
+    }
+    match done_rx.recv() {
+        Ok(res) => Ok(res?),
+        Err(_) => anyhow::bail!("stdin reader exited without a result"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_input_from, read_with_timeout};
+    use std::io::Read;
+    use std::path::Path;
+    use std::time::{Duration, Instant};
+
+    /// Sends nothing for a while, then hits end of input
+    struct Stalled;
+
+    impl Read for Stalled {
+        fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+            std::thread::sleep(Duration::from_millis(200));
+            Ok(0)
+        }
+    }
+
+    /// Sends one line at a time with a pause before every line after the first, like someone
+    /// typing
+    struct Typing {
+        lines: Vec<&'static [u8]>,
+        delay: Duration,
+        started: bool,
+    }
+
+    impl Read for Typing {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.started {
+                std::thread::sleep(self.delay);
+            }
+            self.started = true;
+            if self.lines.is_empty() {
+                return Ok(0);
+            }
+            let line = self.lines.remove(0);
+            buf[..line.len()].copy_from_slice(line);
+            Ok(line.len())
+        }
+    }
+
+    #[test]
+    fn test_empty_closed_stdin_returns_promptly() {
+        let start = Instant::now();
+        let res = read_with_timeout(std::io::empty(), true, Some(Duration::from_secs(5))).unwrap();
+        assert_eq!(res, "");
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_piped_input_ignores_timeout() {
+        let res = read_with_timeout(&b"1\n2\n"[..], false, Some(Duration::ZERO)).unwrap();
+        assert_eq!(res, "1\n2\n");
+    }
+
+    #[test]
+    fn test_stalled_tty_times_out() {
+        let err = read_with_timeout(Stalled, true, Some(Duration::from_millis(50))).unwrap_err();
+        assert!(err.to_string().contains("no input received"));
+    }
+
+    #[test]
+    fn test_slow_tty_input_is_not_cut_off() {
+        let typing = Typing {
+            lines: vec![b"1\n", b"2\n", b"3\n"],
+            delay: Duration::from_millis(100),
+            started: false,
+        };
+        let res = read_with_timeout(typing, true, Some(Duration::from_millis(50))).unwrap();
+        assert_eq!(res, "1\n2\n3\n");
+    }
+
+    #[test]
+    fn test_read_input_from_stdin() {
+        let res = read_input_from(None, &b"1000\n2000\n"[..]).unwrap();
+        assert_eq!(res, "1000\n2000\n");
+    }
+
+    #[test]
+    fn test_read_input_from_file() {
+        let path = std::env::temp_dir().join(format!("aoclib-input-{}.txt", std::process::id()));
+        std::fs::write(&path, "from a file\n").unwrap();
+        let res = read_input_from(Some(&path), &b"from stdin\n"[..]);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(res.unwrap(), "from a file\n");
+    }
+
+    #[test]
+    fn test_read_input_from_missing_file() {
+        let path = Path::new("/nonexistent/aoc/input.txt");
+        let err = read_input_from(Some(path), std::io::empty()).unwrap_err();
+        assert!(err.to_string().contains("/nonexistent/aoc/input.txt"));
+    }
+}
@@ -0,0 +1,62 @@
+use num_traits::PrimInt;
+
+/// The greatest common divisor of `a` and `b`; always non-negative, and zero only if both are
+pub fn gcd<T: PrimInt>(a: T, b: T) -> T {
+    let (mut a, mut b) = (a, b);
+    while b != T::zero() {
+        (a, b) = (b, a % b);
+    }
+    if a < T::zero() {
+        T::zero() - a
+    } else {
+        a
+    }
+}
+
+/// The least common multiple of `a` and `b`; zero if either is
+pub fn lcm<T: PrimInt>(a: T, b: T) -> T {
+    if a == T::zero() || b == T::zero() {
+        return T::zero();
+    }
+    let lcm = a / gcd(a, b) * b;
+    if lcm < T::zero() {
+        T::zero() - lcm
+    } else {
+        lcm
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{gcd, lcm};
+
+    #[test]
+    fn test_coprime() {
+        assert_eq!(gcd(4u64, 9), 1);
+        assert_eq!(lcm(4u64, 9), 36);
+        assert_eq!(gcd(23i64, 19), 1);
+        assert_eq!(lcm(23i64, 19), 437);
+        assert_eq!(gcd(1usize, 7), 1);
+        assert_eq!(lcm(1usize, 7), 7);
+    }
+
+    #[test]
+    fn test_not_coprime() {
+        assert_eq!(gcd(6u64, 4), 2);
+        assert_eq!(lcm(6u64, 4), 12);
+        assert_eq!(gcd(12i64, 18), 6);
+        assert_eq!(lcm(12i64, 18), 36);
+        assert_eq!(gcd(5usize, 5), 5);
+        assert_eq!(lcm(5usize, 5), 5);
+        assert_eq!(lcm(6usize, 4), lcm(4usize, 6));
+    }
+
+    #[test]
+    fn test_zero_and_negative() {
+        assert_eq!(gcd(0i64, 5), 5);
+        assert_eq!(gcd(0i64, 0), 0);
+        assert_eq!(lcm(0i64, 5), 0);
+        assert_eq!(gcd(-12i64, 18), 6);
+        assert_eq!(lcm(-4i64, 6), 12);
+    }
+}
@@ -1,6 +1,20 @@
+mod args;
+pub mod days;
+mod direction;
+mod flood;
 mod grid;
+mod input;
+pub mod math;
 mod point;
+mod point3;
+pub mod ranges;
 
+pub use args::{CommonArgs, SolveTimer};
+pub use direction::Direction;
+pub use flood::{flood_fill, Fill};
+pub use grid::parse_digit_grid;
 pub use grid::DenseGrid;
 pub use grid::HasEmpty;
+pub use input::{read_input, read_input_from, read_stdin};
 pub use point::Point;
+pub use point3::Point3;
@@ -50,6 +50,26 @@ impl<I: DimVal> Point<I> {
             .to_u64()
             .unwrap() as usize
     }
+
+    /// Every point exactly `radius` away from this one by manhattan distance (the edge of a
+    /// diamond), each once. A radius of zero is just this point; a negative one is nothing.
+    pub fn manhattan_ring(&self, radius: I) -> impl Iterator<Item = Point<I>> {
+        let center = *self;
+        let ring = std::iter::successors(Some(I::zero()), |k| Some(*k + I::one()))
+            .take_while(move |k| *k < radius)
+            .flat_map(move |k| {
+                let rest = radius - k;
+                [
+                    Point::new(center.x + rest, center.y + k),
+                    Point::new(center.x - k, center.y + rest),
+                    Point::new(center.x - rest, center.y - k),
+                    Point::new(center.x + k, center.y - rest),
+                ]
+            });
+        std::iter::once(center)
+            .filter(move |_| radius.is_zero())
+            .chain(ring)
+    }
 }
 
 impl fmt::Display for Point {
@@ -167,4 +187,20 @@ mod tests {
         assert_eq!(points[5], Point::new(5, 0));
         assert_eq!(points[10], Point::new(10, 0));
     }
+
+    #[test]
+    fn test_manhattan_ring() {
+        let center = Point::new(3, -2);
+        assert_eq!(center.manhattan_ring(0).collect::<Vec<_>>(), vec![center]);
+        assert_eq!(center.manhattan_ring(-1).count(), 0);
+        for radius in 1..6 {
+            let ring = center.manhattan_ring(radius).collect::<Vec<_>>();
+            assert_eq!(ring.len(), 4 * radius as usize);
+            assert!(ring
+                .iter()
+                .all(|p| p.manhattan_distance_to(center) == radius as usize));
+            let unique = ring.iter().collect::<std::collections::HashSet<_>>();
+            assert_eq!(unique.len(), ring.len());
+        }
+    }
 }
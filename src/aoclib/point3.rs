@@ -0,0 +1,182 @@
+use std::fmt;
+
+use super::point::DimVal;
+
+/// A point in three dimensions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point3<I: DimVal = i64> {
+    pub x: I,
+    pub y: I,
+    pub z: I,
+}
+
+impl<I: DimVal> Point3<I> {
+    pub const fn new(x: I, y: I, z: I) -> Self {
+        Point3 { x, y, z }
+    }
+
+    /// Every point from here to `other`, inclusive. Panics unless the two points differ along
+    /// exactly one axis (or not at all).
+    pub fn line_to(&self, other: Point3<I>) -> impl Iterator<Item = Point3<I>> {
+        LineToIter::new(*self, other, false)
+    }
+
+    /// Like `line_to`, but also allows 45° diagonals, where every axis that changes changes
+    /// by the same amount
+    pub fn diagonal_line_to(&self, other: Point3<I>) -> impl Iterator<Item = Point3<I>> {
+        LineToIter::new(*self, other, true)
+    }
+
+    pub fn manhattan_distance_to(&self, other: Point3<I>) -> usize {
+        ((self.x - other.x).abs() + (self.y - other.y).abs() + (self.z - other.z).abs())
+            .to_u64()
+            .unwrap() as usize
+    }
+}
+
+impl fmt::Display for Point3 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {}, {})", self.x, self.y, self.z)
+    }
+}
+
+impl<I: DimVal> std::ops::Add for Point3<I> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Point3 {
+            x: self.x + other.x,
+            y: self.y + other.y,
+            z: self.z + other.z,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct LineToIter<I: DimVal> {
+    next: Point3<I>,
+    direction: Point3<I>,
+    remaining: usize,
+}
+
+impl<I: DimVal> LineToIter<I> {
+    fn new(start: Point3<I>, end: Point3<I>, diagonals: bool) -> Self {
+        let deltas = [end.x - start.x, end.y - start.y, end.z - start.z];
+        let length = deltas.iter().map(|d| d.abs()).max().unwrap();
+        let moving = deltas.iter().filter(|d| !d.is_zero()).count();
+        if diagonals {
+            assert!(
+                deltas.iter().all(|d| d.is_zero() || d.abs() == length),
+                "{:?} -> {:?} is not a straight or diagonal line",
+                start,
+                end
+            );
+        } else {
+            assert!(moving <= 1, "{:?} -> {:?} is not axis-aligned", start, end);
+        }
+        Self {
+            next: start,
+            direction: Point3::new(deltas[0].signum(), deltas[1].signum(), deltas[2].signum()),
+            remaining: length.to_u64().unwrap() as usize + 1,
+        }
+    }
+}
+
+impl<I: DimVal> Iterator for LineToIter<I> {
+    type Item = Point3<I>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let current = self.next;
+        if self.remaining > 0 {
+            self.next = self.next + self.direction;
+        }
+        Some(current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Point3;
+
+    fn check_line(start: Point3, end: Point3, length: usize) {
+        let points = start.line_to(end).collect::<Vec<_>>();
+        assert_eq!(points.len(), length);
+        assert_eq!(points[0], start);
+        assert_eq!(points[length - 1], end);
+        for pair in points.windows(2) {
+            assert_eq!(pair[0].manhattan_distance_to(pair[1]), 1);
+        }
+
+        let mut other_dir = end.line_to(start).collect::<Vec<_>>();
+        other_dir.reverse();
+        assert_eq!(points, other_dir);
+    }
+
+    #[test]
+    fn test_line_to_x() {
+        check_line(Point3::new(0, 1, 2), Point3::new(10, 1, 2), 11);
+        let points = Point3::new(0, 1, 2)
+            .line_to(Point3::new(10, 1, 2))
+            .collect::<Vec<_>>();
+        assert_eq!(points[5], Point3::new(5, 1, 2));
+    }
+
+    #[test]
+    fn test_line_to_y() {
+        check_line(Point3::new(3, -4, 0), Point3::new(3, 4, 0), 9);
+    }
+
+    #[test]
+    fn test_line_to_z() {
+        check_line(Point3::new(-1, -1, 5), Point3::new(-1, -1, -5), 11);
+    }
+
+    #[test]
+    fn test_line_to_self() {
+        let p = Point3::new(1, 2, 3);
+        assert_eq!(p.line_to(p).collect::<Vec<_>>(), vec![p]);
+    }
+
+    #[test]
+    #[should_panic(expected = "not axis-aligned")]
+    fn test_line_to_not_aligned() {
+        Point3::new(0, 0, 0).line_to(Point3::new(1, 1, 0)).count();
+    }
+
+    #[test]
+    fn test_diagonal_line_to() {
+        let points = Point3::new(0, 0, 0)
+            .diagonal_line_to(Point3::new(2, -2, 2))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            points,
+            vec![
+                Point3::new(0, 0, 0),
+                Point3::new(1, -1, 1),
+                Point3::new(2, -2, 2)
+            ]
+        );
+        let points = Point3::new(0, 0, 0)
+            .diagonal_line_to(Point3::new(0, 3, 3))
+            .collect::<Vec<_>>();
+        assert_eq!(points.len(), 4);
+        assert_eq!(
+            Point3::new(0, 0, 0)
+                .diagonal_line_to(Point3::new(0, 0, 3))
+                .count(),
+            4
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "not a straight or diagonal line")]
+    fn test_diagonal_line_to_uneven() {
+        Point3::new(0, 0, 0)
+            .diagonal_line_to(Point3::new(1, 2, 0))
+            .count();
+    }
+}
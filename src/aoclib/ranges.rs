@@ -0,0 +1,143 @@
+use std::ops::RangeInclusive;
+
+use num_traits::PrimInt;
+
+/// Sort `ranges` and merge any that overlap or touch (e.g. `1..=3` and `4..=6`) in place,
+/// leaving the smallest set of disjoint ranges covering the same values
+pub fn merge_ranges<T: PrimInt>(ranges: &mut Vec<RangeInclusive<T>>) {
+    if ranges.len() < 2 {
+        return;
+    }
+    ranges.sort_by_key(|r| *r.start());
+    let (mut current, mut current_index) = (ranges[0].clone(), 0);
+    for i in 1..ranges.len() {
+        let this = ranges[i].clone();
+        if *this.start() <= current.end().saturating_add(T::one()) {
+            current = *current.start()..=std::cmp::max(*this.end(), *current.end());
+            ranges[current_index] = current.clone();
+        } else {
+            current_index += 1;
+            current = this.clone();
+            ranges[current_index] = this;
+        }
+    }
+    ranges.truncate(current_index + 1);
+}
+
+fn merged<T: PrimInt>(ranges: &[RangeInclusive<T>]) -> Vec<RangeInclusive<T>> {
+    let mut ranges = ranges.to_vec();
+    merge_ranges(&mut ranges);
+    ranges
+}
+
+/// The number of distinct values covered by any of `ranges`, which may overlap and need
+/// not be sorted
+///
+/// Panics if the count doesn't fit in a u64 (i.e. the whole of `i64` or `u64` is covered)
+pub fn coverage_len<T: PrimInt>(ranges: &[RangeInclusive<T>]) -> u64 {
+    merged(ranges)
+        .into_iter()
+        .filter(|r| !r.is_empty())
+        .map(|r| {
+            let start = r.start().to_i128().expect("range start fits in i128");
+            let end = r.end().to_i128().expect("range end fits in i128");
+            u64::try_from(end - start + 1).expect("range length fits in u64")
+        })
+        .fold(0u64, |acc, len| {
+            acc.checked_add(len).expect("coverage fits in u64")
+        })
+}
+
+/// Whether any value in `min..=max` is not covered by one of `ranges`, which may overlap
+/// and need not be sorted
+pub fn has_gap_in<T: PrimInt>(ranges: &[RangeInclusive<T>], min: T, max: T) -> bool {
+    if min > max {
+        return false;
+    }
+    let mut next_uncovered = min;
+    for range in merged(ranges) {
+        if *range.end() < next_uncovered || range.is_empty() {
+            continue;
+        }
+        if *range.start() > next_uncovered {
+            return true;
+        }
+        if *range.end() >= max {
+            return false;
+        }
+        next_uncovered = *range.end() + T::one();
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{coverage_len, has_gap_in, merge_ranges};
+    use std::ops::RangeInclusive;
+
+    fn merged(mut ranges: Vec<RangeInclusive<i64>>) -> Vec<RangeInclusive<i64>> {
+        merge_ranges(&mut ranges);
+        ranges
+    }
+
+    #[test]
+    fn test_adjacent() {
+        assert_eq!(merged(vec![1..=3, 4..=6]), vec![1..=6]);
+        assert_eq!(merged(vec![4..=6, 1..=3, 7..=7]), vec![1..=7]);
+    }
+
+    #[test]
+    fn test_overlapping() {
+        assert_eq!(merged(vec![1..=5, 3..=8]), vec![1..=8]);
+        assert_eq!(merged(vec![-10..=10, -2..=2]), vec![-10..=10]);
+        assert_eq!(merged(vec![5..=9, 0..=5, 2..=3]), vec![0..=9]);
+    }
+
+    #[test]
+    fn test_disjoint() {
+        assert_eq!(merged(vec![8..=9, 1..=2, 4..=6]), vec![1..=2, 4..=6, 8..=9]);
+        assert_eq!(merged(vec![-3..=13, 15..=25]), vec![-3..=13, 15..=25]);
+    }
+
+    #[test]
+    fn test_trivial() {
+        assert_eq!(merged(vec![]), vec![]);
+        assert_eq!(merged(vec![2..=2]), vec![2..=2]);
+        let mut at_limit = vec![0..=u8::MAX, 3..=4];
+        merge_ranges(&mut at_limit);
+        assert_eq!(at_limit, vec![0..=u8::MAX]);
+    }
+
+    #[test]
+    fn test_coverage_len() {
+        assert_eq!(coverage_len::<i64>(&[]), 0);
+        assert_eq!(coverage_len(&[3..=3]), 1);
+        assert_eq!(coverage_len(&[-2..=24]), 27);
+        assert_eq!(coverage_len(&[0..=10, 2..=4]), 11);
+        assert_eq!(coverage_len(&[6..=9, 1..=5]), 9);
+        assert_eq!(coverage_len(&[-3..=13, 15..=25, 0..=1]), 28);
+        assert_eq!(coverage_len(&[0..=u8::MAX]), 256);
+        assert_eq!(coverage_len(&[i64::MIN..=-1]), 1 << 63);
+    }
+
+    #[test]
+    fn test_has_gap_in() {
+        assert!(has_gap_in::<i64>(&[], 0, 0));
+        assert!(!has_gap_in::<i64>(&[], 1, 0));
+        // single range
+        assert!(!has_gap_in(&[0..=20], 0, 20));
+        assert!(!has_gap_in(&[-5..=25], 0, 20));
+        assert!(has_gap_in(&[1..=20], 0, 20));
+        assert!(has_gap_in(&[0..=19], 0, 20));
+        // fully nested
+        assert!(!has_gap_in(&[0..=20, 5..=6], 0, 20));
+        assert!(has_gap_in(&[5..=6, 0..=3], 0, 6));
+        // touching at endpoints
+        assert!(!has_gap_in(&[6..=20, 0..=5], 0, 20));
+        assert!(has_gap_in(&[0..=5, 7..=20], 0, 20));
+        assert!(!has_gap_in(&[0..=5, 7..=20], 7, 20));
+        // ranges entirely outside the window don't count
+        assert!(has_gap_in(&[-10..=-1, 21..=30], 0, 20));
+        assert!(!has_gap_in(&[0..=u8::MAX], 0, u8::MAX));
+    }
+}
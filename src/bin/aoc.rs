@@ -0,0 +1,31 @@
+use aoclib::days;
+use clap::Parser;
+
+/// Run any day that has a library solver
+#[derive(Debug, Parser)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[arg(value_parser = clap::value_parser!(u32).range(1..=25))]
+    day: u32,
+    #[arg(value_parser = clap::value_parser!(u32).range(1..=2))]
+    part: u32,
+    #[command(flatten)]
+    common: aoclib::CommonArgs,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let solver = days::solver(args.day, args.part).ok_or_else(|| {
+        let available = days::SOLVERS.iter().map(|(day, _, _)| day.to_string());
+        anyhow::anyhow!(
+            "day {} isn't available here (try the aoc{} binary); days with library solvers are {}",
+            args.day,
+            args.day,
+            available.collect::<Vec<_>>().join(", ")
+        )
+    })?;
+    let input = args.common.read_input()?;
+    let _timer = args.common.start_timer();
+    println!("{}", solver(&input)?);
+    Ok(())
+}
@@ -1,66 +1,33 @@
-use std::io::BufRead;
+use aoclib::days::day1::read_best;
+use clap::Parser;
 
-type ElfId = u32;
-
-#[derive(Debug)]
-struct Best<const N: usize> {
-    inner: [Option<(ElfId, u64)>; N],
-}
-
-impl<const N: usize> Best<N> {
-    fn new() -> Self {
-        Best { inner: [None; N] }
-    }
-
-    fn handle(&mut self, elf_id: ElfId, calories: u64) {
-        let insert_index = self.inner.iter().position(|i| match i {
-            None => true,
-            Some((_, v)) => *v <= calories,
-        });
-        if let Some(index) = insert_index {
-            // backshift all the other elements
-            if index + 1 < self.inner.len() {
-                for source in (index..(self.inner.len() - 1)).rev() {
-                    self.inner[source + 1] = self.inner[source]
-                }
-            }
-            self.inner[index] = Some((elf_id, calories));
-        }
-    }
-
-    fn total(&self) -> u64 {
-        self.inner
-            .iter()
-            .map(|i| match i {
-                None => 0,
-                Some((_, v)) => *v,
-            })
-            .sum()
-    }
+#[derive(Debug, Parser)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// How many of the best-stocked elves to sum
+    #[arg(short, long, default_value_t = 3, value_parser = clap::value_parser!(u64).range(1..))]
+    top: u64,
+    /// List each retained elf and its total, and say which one carries the most
+    #[arg(short, long)]
+    verbose: bool,
+    #[command(flatten)]
+    common: aoclib::CommonArgs,
 }
 
-fn main() {
-    let stdin = std::io::stdin();
-    let mut handle = stdin.lock();
-    let mut buffer = String::new();
-    let mut best = Best::<3>::new();
-    let mut acc = 0u64;
-    let mut current = 1u32;
-
-    while let Ok(sz) = handle.read_line(&mut buffer) {
-        if sz == 0 {
-            break;
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let input = args.common.read_input()?;
+    let _timer = args.common.start_timer();
+    let best = read_best(input.as_bytes(), args.top as usize)?;
+    if args.verbose {
+        let ranked = best.ranked();
+        for (elf_id, calories) in ranked.iter() {
+            println!("elf {}: {}", elf_id, calories);
         }
-        let val = buffer.trim();
-        if val.is_empty() {
-            best.handle(current, acc);
-            acc = 0;
-            current += 1;
-        } else {
-            acc += val.parse::<u64>().unwrap();
+        if let Some((elf_id, calories)) = ranked.first() {
+            println!("biggest: elf {} with {}", elf_id, calories);
         }
-        buffer.clear();
     }
-    best.handle(current, acc);
     println!("{:?}", best.total());
+    Ok(())
 }
@@ -1,5 +1,4 @@
 use std::collections::{BTreeMap, VecDeque};
-use std::io::BufRead;
 use std::str::FromStr;
 
 use clap::{Parser, ValueEnum};
@@ -17,6 +16,23 @@ enum Mode {
 struct Args {
     #[clap(short, long, value_enum)]
     mode: Mode,
+    /// In part 2, print the letters on the screen instead of drawing it
+    #[clap(long)]
+    ocr: bool,
+    /// The first cycle to sample the signal strength during
+    #[clap(long, default_value_t = 20)]
+    first_sample: Clock,
+    /// How many cycles to wait between signal strength samples
+    #[clap(long, default_value_t = 40, value_parser = clap::value_parser!(Clock).range(1..))]
+    sample_interval: Clock,
+    /// How many pixels wide the CRT is
+    #[clap(long, default_value_t = 40, value_parser = clap::value_parser!(u16).range(1..))]
+    crt_width: u16,
+    /// How many pixels tall the CRT is
+    #[clap(long, default_value_t = 6, value_parser = clap::value_parser!(u16).range(1..))]
+    crt_height: u16,
+    #[command(flatten)]
+    common: aoclib::CommonArgs,
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -117,6 +133,40 @@ impl Cpu {
     }
 }
 
+const GLYPH_WIDTH: usize = 4;
+const GLYPH_SPACING: usize = 5;
+
+/// The capital letters that show up in AoC's CRT puzzles, one string per row
+const FONT: &[(char, [&str; 6])] = &[
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+/// Pack a glyph into an integer, one bit per pixel, reading across each row in turn
+fn glyph_bits<I: IntoIterator<Item = bool>>(pixels: I) -> u32 {
+    pixels
+        .into_iter()
+        .fold(0, |acc, lit| (acc << 1) | u32::from(lit))
+}
+
+fn font_glyph(rows: &[&str; 6]) -> u32 {
+    glyph_bits(rows.iter().flat_map(|row| row.chars().map(|c| c == '#')))
+}
+
 #[derive(Debug)]
 struct CrtDisplay {
     framebuffer: Vec<Vec<bool>>,
@@ -140,11 +190,7 @@ impl CrtDisplay {
     fn tick(&mut self, sprite_x: i32) {
         let y = self.current_y as usize;
         let x = self.current_x as usize;
-        if ((self.current_x as i32) - sprite_x).abs() <= 1 {
-            self.framebuffer[y][x] = true;
-        } else {
-            self.framebuffer[y][x] = false;
-        }
+        self.framebuffer[y][x] = ((self.current_x as i32) - sprite_x).abs() <= 1;
         if self.current_x == self.width - 1 {
             self.current_x = 0;
             self.current_y = (self.current_y + 1) % self.height;
@@ -153,6 +199,24 @@ impl CrtDisplay {
         }
     }
 
+    /// Read the letters off the screen; anything that isn't a known letter comes out as '?'
+    fn ocr(&self) -> String {
+        (0..self.width as usize / GLYPH_SPACING)
+            .map(|i| {
+                let left = i * GLYPH_SPACING;
+                let bits = glyph_bits(
+                    self.framebuffer
+                        .iter()
+                        .flat_map(|row| row[left..left + GLYPH_WIDTH].iter().copied()),
+                );
+                FONT.iter()
+                    .find(|(_, rows)| font_glyph(rows) == bits)
+                    .map(|(c, _)| *c)
+                    .unwrap_or('?')
+            })
+            .collect()
+    }
+
     fn draw(&self) {
         for row in &self.framebuffer {
             println!(
@@ -165,23 +229,23 @@ impl CrtDisplay {
     }
 }
 
-fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
-    let stdin_r = std::io::stdin();
-    let stdin = stdin_r.lock();
+/// Run the program to completion, returning the sum of the sampled signal strengths and the
+/// final state of a `crt_size.0` by `crt_size.1` display
+fn run(
+    mut commands: VecDeque<Op>,
+    first_sample: Clock,
+    sample_interval: Clock,
+    crt_size: (u16, u16),
+) -> (i32, CrtDisplay) {
     let mut cpu = Cpu::new();
-    let mut display = CrtDisplay::new(40, 6);
-    let mut commands = stdin
-        .lines()
-        .map(|line| line?.parse())
-        .collect::<anyhow::Result<VecDeque<_>>>()?;
-    let mut next_sample = 20;
+    let mut display = CrtDisplay::new(crt_size.0, crt_size.1);
+    let mut next_sample = first_sample;
     let mut samples = vec![];
     while !commands.is_empty() {
         if cpu.clock == next_sample {
             let xval = cpu.regval(Register::X);
             samples.push(xval * (next_sample as i32));
-            next_sample += 40;
+            next_sample += sample_interval;
         }
         cpu.tick();
         let sprite_x = cpu.regval(Register::X);
@@ -191,10 +255,300 @@ fn main() -> anyhow::Result<()> {
             cpu.start(command);
         }
     }
+    (samples.into_iter().sum(), display)
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let input = args.common.read_input()?;
+    let _timer = args.common.start_timer();
+    let commands = input
+        .lines()
+        .map(|line| line.parse())
+        .collect::<anyhow::Result<VecDeque<_>>>()?;
+    let (signal_strength, display) = run(
+        commands,
+        args.first_sample,
+        args.sample_interval,
+        (args.crt_width, args.crt_height),
+    );
     if args.mode == Mode::Part1 {
-        println!("{}", samples.into_iter().sum::<i32>());
+        println!("{}", signal_strength);
+    } else if args.ocr {
+        println!("{}", display.ocr());
     } else {
         display.draw();
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{run, Args, Op, FONT};
+    use clap::Parser;
+    use std::collections::VecDeque;
+
+    const SAMPLE: &str = "addx 15
+addx -11
+addx 6
+addx -3
+addx 5
+addx -1
+addx -8
+addx 13
+addx 4
+noop
+addx -1
+addx 5
+addx -1
+addx 5
+addx -1
+addx 5
+addx -1
+addx 5
+addx -1
+addx -35
+addx 1
+addx 24
+addx -19
+addx 1
+addx 16
+addx -11
+noop
+noop
+addx 21
+addx -15
+noop
+noop
+addx -3
+addx 9
+addx 1
+addx -3
+addx 8
+addx 1
+addx 5
+noop
+noop
+noop
+noop
+noop
+addx -36
+noop
+addx 1
+addx 7
+noop
+noop
+noop
+addx 2
+addx 6
+noop
+noop
+noop
+noop
+noop
+addx 1
+noop
+noop
+addx 7
+addx 1
+noop
+addx -13
+addx 13
+addx 7
+noop
+addx 1
+addx -33
+noop
+noop
+noop
+addx 2
+noop
+noop
+noop
+addx 8
+noop
+addx -1
+addx 2
+addx 1
+noop
+addx 17
+addx -9
+addx 1
+addx 1
+addx -3
+addx 11
+noop
+noop
+addx 1
+noop
+addx 1
+noop
+noop
+addx -13
+addx -19
+addx 1
+addx 3
+addx 26
+addx -30
+addx 12
+addx -1
+addx 3
+addx 1
+noop
+noop
+noop
+addx -9
+addx 18
+addx 1
+addx 2
+noop
+noop
+addx 9
+noop
+noop
+noop
+addx -1
+addx 2
+addx -37
+addx 1
+addx 3
+noop
+addx 15
+addx -21
+addx 22
+addx -6
+addx 1
+noop
+addx 2
+addx 1
+noop
+addx -10
+noop
+noop
+addx 20
+addx 1
+addx 2
+addx 2
+addx -6
+addx -11
+noop
+noop
+noop
+";
+
+    fn parse(s: &str) -> VecDeque<Op> {
+        s.lines().map(|l| l.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_default_samples() {
+        let args = Args::parse_from(["aoc10", "-m", "part1"]);
+        let (signal_strength, _) = run(
+            parse(SAMPLE),
+            args.first_sample,
+            args.sample_interval,
+            (args.crt_width, args.crt_height),
+        );
+        assert_eq!(signal_strength, 13140);
+    }
+
+    #[test]
+    fn test_custom_samples() {
+        // X is 1 during cycles 1 through 3, and 4 during cycles 4 and 5
+        let program = "noop\naddx 3\naddx -5\nnoop\nnoop\n";
+        let (signal_strength, _) = run(parse(program), 1, 2, (40, 6));
+        assert_eq!(signal_strength, 1 + 3 + 4 * 5);
+    }
+
+    #[test]
+    fn test_zero_interval_rejected() {
+        assert!(Args::try_parse_from(["aoc10", "-m", "part1", "--sample-interval", "0"]).is_err());
+    }
+
+    /// A program that draws `rows` on a 40x6 CRT. Every `addx` spans two pixels, so for each
+    /// pair it picks a sprite position that lights exactly the pixels wanted there, and the
+    /// previous `addx` moves the sprite to it.
+    fn program_drawing(rows: &[&str; 6]) -> VecDeque<Op> {
+        let positions = rows
+            .iter()
+            .flat_map(|row| {
+                let pixels = row.chars().map(|c| c == '#').collect::<Vec<_>>();
+                (0..pixels.len()).step_by(2).map(move |x| {
+                    let x = x as i32;
+                    match (pixels[x as usize], pixels[x as usize + 1]) {
+                        (true, true) => x + 1,
+                        (true, false) => x - 1,
+                        (false, true) => x + 2,
+                        (false, false) => x - 3,
+                    }
+                })
+            })
+            .collect::<Vec<i32>>();
+        assert_eq!(positions[0], 1, "the sprite starts at 1");
+        let mut program = positions
+            .iter()
+            .zip(positions.iter().skip(1))
+            .map(|(from, to)| format!("addx {}", to - from))
+            .collect::<Vec<_>>();
+        // the last pair still needs its two cycles, for 240 in all
+        program.extend(["noop"; 2].map(String::from));
+        parse(&program.join("\n"))
+    }
+
+    #[test]
+    fn test_ocr() {
+        let rows = [
+            "####.#....####..###.#..#..##..###..###..",
+            "#....#....#....#....#..#.#..#.#..#.#..#.",
+            "###..#....###..#....####.#..#.#..#.#..#.",
+            "#....#....#.....##..#..#.####.###..###..",
+            "#....#....#.......#.#..#.#..#.#.#..#....",
+            "####.####.#....###..#..#.#..#.#..#.#....",
+        ];
+        let (_, display) = run(program_drawing(&rows), 20, 40, (40, 6));
+        for (row, expected) in display.framebuffer.iter().zip(rows) {
+            let drawn = row
+                .iter()
+                .map(|lit| if *lit { '#' } else { '.' })
+                .collect::<String>();
+            assert_eq!(drawn, expected);
+        }
+        assert_eq!(display.ocr(), "ELFSHARP");
+
+        // the puzzle's sample draws stripes, not letters
+        let (_, display) = run(parse(SAMPLE), 20, 40, (40, 6));
+        assert_eq!(display.ocr(), "????????");
+    }
+
+    #[test]
+    fn test_font_glyphs_are_distinct() {
+        for (i, (a, rows)) in FONT.iter().enumerate() {
+            assert!(rows.iter().all(|r| r.len() == 4), "{}", a);
+            for (b, other) in &FONT[i + 1..] {
+                assert_ne!(rows, other, "{} and {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_other_crt_sizes() {
+        let pixels = |row: &[bool]| {
+            row.iter()
+                .map(|lit| if *lit { '#' } else { '.' })
+                .collect::<String>()
+        };
+        let (_, display) = run(parse(SAMPLE), 20, 40, (40, 6));
+        assert_eq!(pixels(&display.framebuffer[0][..12]), "##..##..##..");
+        assert_eq!(pixels(&display.framebuffer[1][..12]), "###...###...");
+
+        let (_, display) = run(parse(SAMPLE), 20, 40, (80, 6));
+        assert_eq!(display.framebuffer.len(), 6);
+        assert!(display.framebuffer.iter().all(|row| row.len() == 80));
+        // 240 cycles only fill the first three rows of a wider screen
+        assert!(display.framebuffer[3..].iter().flatten().all(|lit| !lit));
+
+        // a tiny screen just keeps wrapping around
+        let (_, display) = run(parse(SAMPLE), 20, 40, (7, 1));
+        assert_eq!(display.framebuffer.len(), 1);
+    }
+}
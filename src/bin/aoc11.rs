@@ -1,16 +1,22 @@
+use std::collections::HashMap;
+use std::hash::Hash;
 use std::str::FromStr;
 
+use aoclib::math::lcm;
 use clap::{Parser, ValueEnum};
 use itertools::Itertools;
 use nom::{
     branch::alt,
     bytes::complete::tag,
     character::complete::{digit1, newline, one_of, space1},
-    combinator::{map, map_res},
+    combinator::{map, map_res, verify},
     multi::separated_list1,
     sequence::{delimited, pair, preceded, separated_pair},
     IResult,
 };
+use num_bigint::BigInt;
+use num_traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, Euclid, Num};
+use petgraph::graph::DiGraph;
 
 #[derive(ValueEnum, Debug, PartialEq, Eq, Clone, Copy)]
 enum Mode {
@@ -27,6 +33,37 @@ struct Args {
     rounds: Option<usize>,
     #[clap(short, long, value_parser)]
     verbose: bool,
+    /// Print the throw targets as a DOT graph instead of simulating
+    #[clap(long)]
+    show_monkey_graph: bool,
+    /// Track exact worry levels instead of reducing them (part 1 only)
+    #[clap(long)]
+    bigint: bool,
+    /// Fast-forward once the items end up back where they were at the start of a round
+    #[clap(long)]
+    detect_cycles: bool,
+    #[command(flatten)]
+    common: aoclib::CommonArgs,
+}
+
+/// A type worry levels can be tracked in
+trait Worry:
+    Num + Clone + Eq + Hash + From<i64> + CheckedAdd + CheckedSub + CheckedMul + CheckedDiv + Euclid
+{
+}
+
+impl<T> Worry for T where
+    T: Num
+        + Clone
+        + Eq
+        + Hash
+        + From<i64>
+        + CheckedAdd
+        + CheckedSub
+        + CheckedMul
+        + CheckedDiv
+        + Euclid
+{
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -36,10 +73,10 @@ enum Operand {
 }
 
 impl Operand {
-    fn value(&self, item: i64) -> i64 {
+    fn value<W: Worry>(&self, item: &W) -> W {
         match self {
-            Self::Old => item,
-            Self::Literal(i) => *i,
+            Self::Old => item.clone(),
+            Self::Literal(i) => W::from(*i),
         }
     }
 }
@@ -47,17 +84,46 @@ impl Operand {
 #[derive(Debug, PartialEq, Eq)]
 enum Op {
     Add,
+    Subtract,
     Multiply,
+    Divide,
 }
 
 impl Op {
-    fn apply(&self, item: i64, operand: &Operand, modulus: i64) -> i64 {
-        let lhs = operand.value(item) % modulus;
-        let rhs = item % modulus;
-        match self {
-            Op::Add => lhs.checked_add(rhs).unwrap() % modulus,
-            Op::Multiply => lhs.checked_mul(rhs).unwrap() % modulus,
+    /// Compute `item <op> operand`, reduced into `0..modulus` if there is one.
+    ///
+    /// Division doesn't survive reduction, so it's an error to divide under a modulus; so are
+    /// dividing by zero (`old / old` on a 0) and a result that doesn't fit in `W`.
+    fn apply<W: Worry>(
+        &self,
+        item: W,
+        operand: &Operand,
+        modulus: Option<&W>,
+    ) -> anyhow::Result<W> {
+        let rhs = operand.value(&item);
+        let (lhs, rhs) = match modulus {
+            Some(modulus) => (item % modulus.clone(), rhs % modulus.clone()),
+            None => (item, rhs),
+        };
+        let value = match self {
+            Op::Add => lhs.checked_add(&rhs),
+            Op::Subtract => lhs.checked_sub(&rhs),
+            Op::Multiply => lhs.checked_mul(&rhs),
+            Op::Divide => {
+                if modulus.is_some() {
+                    anyhow::bail!("cannot divide under a modulus");
+                }
+                if rhs.is_zero() {
+                    anyhow::bail!("division by zero");
+                }
+                lhs.checked_div(&rhs)
+            }
         }
+        .ok_or_else(|| anyhow::anyhow!("unbounded worry: {:?} overflowed", self))?;
+        Ok(match modulus {
+            Some(modulus) => value.rem_euclid(modulus),
+            None => value,
+        })
     }
 }
 
@@ -81,42 +147,65 @@ struct Test {
 }
 
 #[derive(Debug)]
-struct Action {
-    item: i64,
+struct Action<W> {
+    item: W,
     target: usize,
 }
 
 #[derive(Debug)]
-struct Monkey {
+struct Monkey<W = i64> {
     id: usize,
     inspections: usize,
-    items: Vec<i64>,
+    items: Vec<W>,
     operation: Op,
     operand: Operand,
     test: Test,
 }
 
 impl Monkey {
-    fn simulate(&mut self, common_modulus: i64, div_level: bool) -> Vec<Action> {
+    /// Switch to tracking worry levels in a different type
+    fn convert<W: Worry>(self) -> Monkey<W> {
+        Monkey {
+            id: self.id,
+            inspections: self.inspections,
+            items: self.items.into_iter().map(W::from).collect(),
+            operation: self.operation,
+            operand: self.operand,
+            test: self.test,
+        }
+    }
+}
+
+impl<W: Worry> Monkey<W> {
+    fn simulate(
+        &mut self,
+        common_modulus: Option<&W>,
+        div_level: bool,
+    ) -> anyhow::Result<Vec<Action<W>>> {
         self.items
             .drain(0..)
             .map(|item| {
                 self.inspections += 1;
-                let mut new_cost = self.operation.apply(item, &self.operand, common_modulus);
+                let mut new_cost = self
+                    .operation
+                    .apply(item, &self.operand, common_modulus)
+                    .map_err(|e| anyhow::anyhow!("monkey {}: {}", self.id, e))?;
                 if div_level {
-                    new_cost /= 3;
-                }
-                if new_cost % self.test.modulus == 0 {
-                    Action {
-                        item: new_cost,
-                        target: self.test.true_target,
-                    }
-                } else {
-                    Action {
-                        item: new_cost,
-                        target: self.test.false_target,
-                    }
+                    new_cost = new_cost / W::from(3);
                 }
+                Ok(
+                    if (new_cost.clone() % W::from(self.test.modulus)).is_zero() {
+                        Action {
+                            item: new_cost,
+                            target: self.test.true_target,
+                        }
+                    } else {
+                        Action {
+                            item: new_cost,
+                            target: self.test.false_target,
+                        }
+                    },
+                )
             })
             .collect()
     }
@@ -135,16 +224,21 @@ fn parse_monkey(s: &str) -> IResult<&str, Monkey> {
     )(s)?;
     let (s, (op, operand)) = delimited(
         pair(space1, tag("Operation: new = old ")),
-        separated_pair(
-            map_res(one_of("+*"), |s: char| {
-                Ok(match s {
-                    '+' => Op::Add,
-                    '*' => Op::Multiply,
-                    other => anyhow::bail!("invalid operation {}", other),
-                })
-            }),
-            tag(" "),
-            map_res(alt((tag("old"), digit1)), |s: &str| s.parse::<Operand>()),
+        verify(
+            separated_pair(
+                map_res(one_of("+-*/"), |s: char| {
+                    Ok(match s {
+                        '+' => Op::Add,
+                        '-' => Op::Subtract,
+                        '*' => Op::Multiply,
+                        '/' => Op::Divide,
+                        other => anyhow::bail!("invalid operation {}", other),
+                    })
+                }),
+                tag(" "),
+                map_res(alt((tag("old"), digit1)), |s: &str| s.parse::<Operand>()),
+            ),
+            |(op, operand)| !(*op == Op::Divide && *operand == Operand::Literal(0)),
         ),
         newline,
     )(s)?;
@@ -187,12 +281,50 @@ fn parse_monkeys(s: &str) -> anyhow::Result<Vec<Monkey>> {
     Ok(monkeys)
 }
 
-fn simulate_round(monkeys: &mut Vec<Monkey>, common_modulus: i64, div_level: bool) {
+/// Render who throws to whom as DOT, with edges labeled by the test outcome
+fn monkey_graph(monkeys: &[Monkey]) -> String {
+    let mut graph = DiGraph::<usize, &'static str>::new();
+    let nodes = monkeys
+        .iter()
+        .map(|m| (m.id, graph.add_node(m.id)))
+        .collect::<std::collections::HashMap<_, _>>();
+    for monkey in monkeys {
+        let source = nodes[&monkey.id];
+        for (target, label) in [
+            (monkey.test.true_target, "true"),
+            (monkey.test.false_target, "false"),
+        ] {
+            if let Some(target) = nodes.get(&target) {
+                graph.add_edge(source, *target, label);
+            }
+        }
+    }
+    format!("{}", petgraph::dot::Dot::new(&graph))
+}
+
+/// The modulus worry levels can be reduced by without changing any test outcome.
+///
+/// There isn't one if anything divides worry levels, whether a monkey's operation or the
+/// relief after each inspection (`div_level`), since floor division doesn't commute with the
+/// reduction.
+fn common_modulus(monkeys: &[Monkey], div_level: bool) -> Option<i64> {
+    if div_level || monkeys.iter().any(|m| m.operation == Op::Divide) {
+        None
+    } else {
+        Some(monkeys.iter().fold(1, |a, m| lcm(a, m.test.modulus)))
+    }
+}
+
+fn simulate_round<W: Worry>(
+    monkeys: &mut [Monkey<W>],
+    common_modulus: Option<&W>,
+    div_level: bool,
+) -> anyhow::Result<()> {
     for index in 0..monkeys.len() {
         let actions = monkeys
             .get_mut(index)
             .unwrap()
-            .simulate(common_modulus, div_level);
+            .simulate(common_modulus, div_level)?;
         for action in actions {
             monkeys
                 .get_mut(action.target)
@@ -201,14 +333,18 @@ fn simulate_round(monkeys: &mut Vec<Monkey>, common_modulus: i64, div_level: boo
                 .push(action.item);
         }
     }
+    Ok(())
 }
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
-    let stdin_r = std::io::stdin();
-    let input = std::io::read_to_string(stdin_r)?;
-    let mut monkeys = parse_monkeys(input.as_str())?;
-    let common_modulus = monkeys.iter().fold(1, |a, m| a * m.test.modulus);
+    let input = args.common.read_input()?;
+    let _timer = args.common.start_timer();
+    let monkeys = parse_monkeys(input.as_str())?;
+    if args.show_monkey_graph {
+        print!("{}", monkey_graph(&monkeys));
+        return Ok(());
+    }
     let rounds = match args.rounds {
         Some(r) => r,
         None => match args.mode {
@@ -216,9 +352,80 @@ fn main() -> anyhow::Result<()> {
             Mode::Part2 => 10000,
         },
     };
-    for round in 0..rounds {
-        simulate_round(&mut monkeys, common_modulus, args.mode == Mode::Part1);
-        if args.verbose {
+    let div_level = args.mode == Mode::Part1;
+    let result = if args.bigint {
+        if !div_level {
+            anyhow::bail!("--bigint is only supported for part 1");
+        }
+        let mut monkeys = monkeys
+            .into_iter()
+            .map(Monkey::convert::<BigInt>)
+            .collect::<Vec<_>>();
+        monkey_business(
+            &mut monkeys,
+            rounds,
+            div_level,
+            None,
+            args.detect_cycles,
+            args.verbose,
+        )?
+    } else {
+        let mut monkeys = monkeys;
+        let common_modulus = common_modulus(&monkeys, div_level);
+        monkey_business(
+            &mut monkeys,
+            rounds,
+            div_level,
+            common_modulus,
+            args.detect_cycles,
+            args.verbose,
+        )?
+    };
+    println!("{}", result);
+    Ok(())
+}
+
+/// Run the given number of rounds and multiply the two highest inspection counts.
+///
+/// With `detect_cycles`, remember every arrangement of items seen at the start of a round;
+/// throwing is deterministic, so once one repeats the inspection counts just grow by the
+/// same amount every period and the whole periods can be skipped.
+fn monkey_business<W: Worry>(
+    monkeys: &mut [Monkey<W>],
+    rounds: usize,
+    div_level: bool,
+    common_modulus: Option<W>,
+    mut detect_cycles: bool,
+    verbose: bool,
+) -> anyhow::Result<usize> {
+    let mut seen = HashMap::new();
+    let mut round = 0;
+    while round < rounds {
+        if detect_cycles {
+            let state = monkeys.iter().map(|m| m.items.clone()).collect::<Vec<_>>();
+            let inspections = monkeys.iter().map(|m| m.inspections).collect::<Vec<_>>();
+            if let Some((start, start_inspections)) = seen.insert(state, (round, inspections)) {
+                let period = round - start;
+                let periods = (rounds - round) / period;
+                for (monkey, before) in monkeys.iter_mut().zip(start_inspections) {
+                    monkey.inspections += periods * (monkey.inspections - before);
+                }
+                if verbose {
+                    println!(
+                        "rounds {}..{} repeat; skipping {} rounds",
+                        start,
+                        round,
+                        periods * period
+                    );
+                }
+                round += periods * period;
+                detect_cycles = false;
+                seen.clear();
+                continue;
+            }
+        }
+        simulate_round(monkeys, common_modulus.as_ref(), div_level)?;
+        if verbose {
             println!("== After round {} ==", round);
             for monkey in monkeys.iter() {
                 println!(
@@ -227,21 +434,52 @@ fn main() -> anyhow::Result<()> {
                 );
             }
         }
+        round += 1;
     }
-    let too_much: usize = monkeys
+    Ok(monkeys
         .iter()
         .map(|m| m.inspections)
         .sorted()
         .rev()
         .take(2)
-        .product();
-    println!("{}", too_much);
-    Ok(())
+        .product())
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{parse_monkey, Op, Operand};
+    use super::{
+        common_modulus, monkey_business, monkey_graph, parse_monkey, parse_monkeys, BigInt, Monkey,
+        Op, Operand,
+    };
+
+    const SAMPLE: &str = "Monkey 0:
+  Starting items: 79, 98
+  Operation: new = old * 19
+  Test: divisible by 23
+    If true: throw to monkey 2
+    If false: throw to monkey 3
+
+Monkey 1:
+  Starting items: 54, 65, 75, 74
+  Operation: new = old + 6
+  Test: divisible by 19
+    If true: throw to monkey 2
+    If false: throw to monkey 0
+
+Monkey 2:
+  Starting items: 79, 60, 97
+  Operation: new = old * old
+  Test: divisible by 13
+    If true: throw to monkey 1
+    If false: throw to monkey 3
+
+Monkey 3:
+  Starting items: 74
+  Operation: new = old + 3
+  Test: divisible by 17
+    If true: throw to monkey 0
+    If false: throw to monkey 1
+";
 
     #[test]
     fn test_parse_monkey() {
@@ -264,4 +502,190 @@ mod tests {
         assert_eq!(monkey.test.true_target, 2);
         assert_eq!(monkey.test.false_target, 3);
     }
+
+    #[test]
+    fn test_monkey_graph() {
+        let monkeys = parse_monkeys(SAMPLE).unwrap();
+        let dot = monkey_graph(&monkeys);
+        assert!(dot.starts_with("digraph {"));
+        for monkey in monkeys.iter() {
+            assert!(dot.contains(&format!("{} [ label = \"{}\" ]", monkey.id, monkey.id)));
+            for (target, label) in [
+                (monkey.test.true_target, "true"),
+                (monkey.test.false_target, "false"),
+            ] {
+                let edge = format!("{} -> {} [ label = \"{}\" ]", monkey.id, target, label);
+                assert!(dot.contains(&edge), "missing {:?} in {}", edge, dot);
+            }
+        }
+        assert_eq!(dot.matches(" -> ").count(), monkeys.len() * 2);
+    }
+
+    #[test]
+    fn test_parse_subtraction_and_division() {
+        let (_, monkey) = parse_monkey(
+            r#"Monkey 4:
+  Starting items: 10
+  Operation: new = old - 3
+  Test: divisible by 7
+    If true: throw to monkey 0
+    If false: throw to monkey 1
+"#,
+        )
+        .unwrap();
+        assert_eq!(monkey.operation, Op::Subtract);
+        assert_eq!(monkey.operand, Operand::Literal(3));
+        assert_eq!(
+            monkey
+                .operation
+                .apply(10i64, &monkey.operand, None)
+                .unwrap(),
+            7
+        );
+        assert_eq!(
+            monkey
+                .operation
+                .apply(1i64, &monkey.operand, Some(&7))
+                .unwrap(),
+            5
+        );
+
+        let (_, monkey) = parse_monkey(
+            r#"Monkey 5:
+  Starting items: 10
+  Operation: new = old / 4
+  Test: divisible by 7
+    If true: throw to monkey 0
+    If false: throw to monkey 1
+"#,
+        )
+        .unwrap();
+        assert_eq!(monkey.operation, Op::Divide);
+        assert_eq!(
+            monkey
+                .operation
+                .apply(10i64, &monkey.operand, None)
+                .unwrap(),
+            2
+        );
+        assert_eq!(common_modulus(&[monkey], false), None);
+
+        assert!(parse_monkey(
+            r#"Monkey 6:
+  Starting items: 10
+  Operation: new = old / 0
+  Test: divisible by 7
+    If true: throw to monkey 0
+    If false: throw to monkey 1
+"#,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_sample_monkey_business() {
+        let mut monkeys = parse_monkeys(SAMPLE).unwrap();
+        assert_eq!(common_modulus(&monkeys, false), Some(23 * 19 * 13 * 17));
+        assert_eq!(common_modulus(&monkeys, true), None);
+        let modulus = common_modulus(&monkeys, true);
+        assert_eq!(
+            monkey_business(&mut monkeys, 20, true, modulus, false, false).unwrap(),
+            10605
+        );
+        let mut monkeys = parse_monkeys(SAMPLE).unwrap();
+        let modulus = common_modulus(&monkeys, false);
+        assert_eq!(
+            monkey_business(&mut monkeys, 10000, false, modulus, false, false).unwrap(),
+            2713310158
+        );
+    }
+
+    #[test]
+    fn test_bigint_matches_i64() {
+        // part 1's worry levels only grow, so compare well short of where i64 overflows
+        for (rounds, expected) in [(20, 10605), (80, 184450)] {
+            let mut monkeys = parse_monkeys(SAMPLE).unwrap();
+            let modulus = common_modulus(&monkeys, true);
+            let plain = monkey_business(&mut monkeys, rounds, true, modulus, false, false).unwrap();
+            let mut monkeys = parse_monkeys(SAMPLE)
+                .unwrap()
+                .into_iter()
+                .map(Monkey::convert::<BigInt>)
+                .collect::<Vec<_>>();
+            let exact = monkey_business(&mut monkeys, rounds, true, None, false, false).unwrap();
+            assert_eq!(plain, expected);
+            assert_eq!(exact, plain);
+        }
+        // and past that point it's an error rather than a wrong answer
+        let mut monkeys = parse_monkeys(SAMPLE).unwrap();
+        assert!(monkey_business(&mut monkeys, 10000, true, None, false, false).is_err());
+    }
+
+    #[test]
+    fn test_detect_cycles() {
+        // neither part repeats on the sample, so skipping must leave the answers alone
+        for (div_level, rounds, expected) in [(false, 10000, 2713310158), (true, 80, 184450)] {
+            let mut monkeys = parse_monkeys(SAMPLE).unwrap();
+            let modulus = common_modulus(&monkeys, div_level);
+            let plain =
+                monkey_business(&mut monkeys, rounds, div_level, modulus, false, false).unwrap();
+            let mut monkeys = parse_monkeys(SAMPLE).unwrap();
+            let modulus = common_modulus(&monkeys, div_level);
+            let skipped =
+                monkey_business(&mut monkeys, rounds, div_level, modulus, true, false).unwrap();
+            assert_eq!(plain, expected);
+            assert_eq!(skipped, plain);
+        }
+    }
+
+    #[test]
+    fn test_unbounded_worry() {
+        // squaring with a divider around means nothing keeps part 2's worry levels in check
+        let input = r#"Monkey 0:
+  Starting items: 79
+  Operation: new = old * old
+  Test: divisible by 2
+    If true: throw to monkey 1
+    If false: throw to monkey 1
+
+Monkey 1:
+  Starting items: 10
+  Operation: new = old / 2
+  Test: divisible by 3
+    If true: throw to monkey 0
+    If false: throw to monkey 0
+"#;
+        let mut monkeys = parse_monkeys(input).unwrap();
+        let modulus = common_modulus(&monkeys, false);
+        assert_eq!(modulus, None);
+        let err = monkey_business(&mut monkeys, 10000, false, modulus, false, false).unwrap_err();
+        assert!(err.to_string().contains("unbounded worry"), "{}", err);
+
+        let (_, monkey) = parse_monkey(input).unwrap();
+        assert!(Op::Divide.apply(10i64, &monkey.operand, Some(&7)).is_err());
+    }
+
+    #[test]
+    fn test_divide_by_zero_item() {
+        let mut monkeys = parse_monkeys(
+            r#"Monkey 0:
+  Starting items: 4
+  Operation: new = old - 4
+  Test: divisible by 2
+    If true: throw to monkey 1
+    If false: throw to monkey 1
+
+Monkey 1:
+  Starting items: 1
+  Operation: new = old / old
+  Test: divisible by 3
+    If true: throw to monkey 0
+    If false: throw to monkey 0
+"#,
+        )
+        .unwrap();
+        let err = monkey_business(&mut monkeys, 1, false, None, false, false).unwrap_err();
+        assert_eq!(err.to_string(), "monkey 1: division by zero");
+        assert!(Op::Divide.apply(0i64, &Operand::Old, None).is_err());
+    }
 }
@@ -18,6 +18,14 @@ struct Args {
     mode: Mode,
     #[clap(short, long, value_parser)]
     output_dot: Option<std::path::PathBuf>,
+    /// Draw the route taken over the grid before printing its length
+    #[clap(long)]
+    path: bool,
+    /// Allow diagonal steps as well, under the same climbing rule
+    #[clap(long)]
+    diagonal: bool,
+    #[command(flatten)]
+    common: aoclib::CommonArgs,
 }
 
 #[derive(Debug)]
@@ -30,26 +38,85 @@ struct Grid {
 }
 
 impl Grid {
-    fn shortest_path(&self) -> usize {
+    fn shortest_path(&self) -> Option<usize> {
         let start_index = *self.graph_indices.get(&self.start_coordinate).unwrap();
         let end_index = *self.graph_indices.get(&self.end_coordinate).unwrap();
         let dk =
             petgraph::algo::dijkstra::dijkstra(&self.graph, start_index, Some(end_index), |_| 1);
-        *dk.get(&end_index).unwrap()
+        dk.get(&end_index).copied()
     }
 
-    fn shortest_paths_any_start(&self) -> usize {
+    fn shortest_paths_any_start(&self) -> Option<usize> {
         let mut rev_graph = self.graph.clone();
         rev_graph.reverse();
         let end_index = *self.graph_indices.get(&self.end_coordinate).unwrap();
         let dres = petgraph::algo::dijkstra::dijkstra(&rev_graph, end_index, None, |_| 1);
-        *self
-            .graph_indices
+        self.graph_indices
             .iter()
             .filter(|((x, y), _i)| self.cells[*y][*x] == 0)
             .filter_map(|(_xy, i)| dres.get(i))
             .min()
-            .unwrap()
+            .copied()
+    }
+
+    /// The cells along a shortest route from the start to the end, inclusive, or None if the
+    /// end can't be reached
+    fn shortest_route(&self) -> Option<Vec<(usize, usize)>> {
+        let start_index = *self.graph_indices.get(&self.start_coordinate).unwrap();
+        let end_index = *self.graph_indices.get(&self.end_coordinate).unwrap();
+        let (_, path) =
+            petgraph::algo::astar(&self.graph, start_index, |n| n == end_index, |_| 1, |_| 0)?;
+        Some(path.into_iter().map(|i| self.graph[i]).collect())
+    }
+
+    /// The cells along a shortest route from any lowest cell to the end, inclusive, or None if
+    /// the end can't be reached from any of them
+    fn shortest_route_any_start(&self) -> Option<Vec<(usize, usize)>> {
+        let mut rev_graph = self.graph.clone();
+        rev_graph.reverse();
+        let end_index = *self.graph_indices.get(&self.end_coordinate).unwrap();
+        let (_, path) = petgraph::algo::astar(
+            &rev_graph,
+            end_index,
+            |n| {
+                let (x, y) = rev_graph[n];
+                self.cells[y][x] == 0
+            },
+            |_| 1,
+            |_| 0,
+        )?;
+        Some(path.into_iter().rev().map(|i| self.graph[i]).collect())
+    }
+
+    /// Draw `route` over the grid, with an arrow in each cell pointing to the next one
+    fn render_route(&self, route: &[(usize, usize)]) -> String {
+        let mut rows = self
+            .cells
+            .iter()
+            .map(|row| vec!['.'; row.len()])
+            .collect::<Vec<_>>();
+        for (from, to) in route.iter().zip(route.iter().skip(1)) {
+            rows[from.1][from.0] = match (to.0 as i64 - from.0 as i64, to.1 as i64 - from.1 as i64)
+            {
+                (0, -1) => '^',
+                (0, 1) => 'v',
+                (-1, 0) => '<',
+                (1, 0) => '>',
+                (-1, -1) => '↖',
+                (1, -1) => '↗',
+                (-1, 1) => '↙',
+                (1, 1) => '↘',
+                step => unreachable!("route takes a step of {:?}", step),
+            };
+        }
+        rows[self.end_coordinate.1][self.end_coordinate.0] = 'E';
+        rows.into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .chain(std::iter::once('\n'))
+                    .collect::<String>()
+            })
+            .collect()
     }
 }
 
@@ -57,6 +124,14 @@ impl FromStr for Grid {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Grid::parse_with(s, false)
+    }
+}
+
+impl Grid {
+    /// Parse the heightmap; if `diagonal` is set, steps to the four diagonal neighbors are
+    /// allowed too
+    fn parse_with(s: &str, diagonal: bool) -> anyhow::Result<Self> {
         let mut start_coordinate = None;
         let mut end_coordinate = None;
         let cells = s
@@ -64,22 +139,21 @@ impl FromStr for Grid {
             .filter(|r| !r.is_empty())
             .enumerate()
             .map(|(y, row)| {
-                row.as_bytes()
-                    .iter()
+                row.chars()
                     .enumerate()
                     .map(|(x, cell)| {
                         let height = match cell {
-                            b'S' => {
+                            'S' => {
                                 start_coordinate = Some((x, y));
                                 0
                             }
-                            b'E' => {
+                            'E' => {
                                 end_coordinate = Some((x, y));
                                 25
                             }
-                            other if *other >= b'a' && *other <= b'z' => *other - b'a',
+                            'a'..='z' => cell as u8 - b'a',
                             other => {
-                                anyhow::bail!("invalid char {}", other);
+                                anyhow::bail!("invalid char {:?} at ({}, {})", other, x, y);
                             }
                         };
                         Ok(height)
@@ -99,8 +173,8 @@ impl FromStr for Grid {
         for ((x, y), this_node) in coordinates.iter().map(|((x, y), i)| ((*x, *y), *i)) {
             for possible_x in x.saturating_sub(1)..=min(x + 1, cells[y].len() - 1) {
                 for possible_y in y.saturating_sub(1)..=min(y + 1, height - 1) {
-                    if (possible_x == x || possible_y == y) && ((possible_x, possible_y) != (x, y))
-                    {
+                    let orthogonal = possible_x == x || possible_y == y;
+                    if (diagonal || orthogonal) && ((possible_x, possible_y) != (x, y)) {
                         let neighbor_node = *coordinates.get(&(possible_x, possible_y)).unwrap();
                         let neighbor_val = cells[possible_y][possible_x];
                         let my_val = cells[y][x];
@@ -124,9 +198,9 @@ impl FromStr for Grid {
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
-    let stdin = std::io::stdin();
-    let input = std::io::read_to_string(stdin)?;
-    let grid = input.parse::<Grid>()?;
+    let input = args.common.read_input()?;
+    let _timer = args.common.start_timer();
+    let grid = Grid::parse_with(&input, args.diagonal)?;
     if let Some(output_path) = args.output_dot {
         let graph = format!(
             "{:?}",
@@ -134,10 +208,117 @@ fn main() -> anyhow::Result<()> {
         );
         std::fs::write(output_path, graph)?;
     }
+    if args.path {
+        let route = match args.mode {
+            Mode::Part1 => grid.shortest_route(),
+            Mode::Part2 => grid.shortest_route_any_start(),
+        }
+        .ok_or_else(|| anyhow::anyhow!("no route"))?;
+        print!("{}", grid.render_route(&route));
+    }
     let res = match args.mode {
         Mode::Part1 => grid.shortest_path(),
         Mode::Part2 => grid.shortest_paths_any_start(),
-    };
+    }
+    .ok_or_else(|| anyhow::anyhow!("no route"))?;
     println!("{:?}", res);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Grid;
+
+    const SAMPLE: &str = "Sabqponm
+abcryxxl
+accszExk
+acctuvwj
+abdefghi
+";
+
+    #[test]
+    fn test_sample() {
+        let grid = SAMPLE.parse::<Grid>().unwrap();
+        assert_eq!(grid.shortest_path().unwrap(), 31);
+        assert_eq!(grid.shortest_paths_any_start().unwrap(), 29);
+    }
+
+    #[test]
+    fn test_invalid_char_position() {
+        let err = "Sabq\nab?E\n".parse::<Grid>().unwrap_err();
+        assert_eq!(err.to_string(), "invalid char '?' at (2, 1)");
+        // columns count characters, not bytes
+        let err = "Sab\nébE\n".parse::<Grid>().unwrap_err();
+        assert_eq!(err.to_string(), "invalid char 'é' at (0, 1)");
+        let err = "Sab\naéE\n".parse::<Grid>().unwrap_err();
+        assert_eq!(err.to_string(), "invalid char 'é' at (1, 1)");
+    }
+
+    #[test]
+    fn test_route_matches_shortest_path() {
+        let grid = SAMPLE.parse::<Grid>().unwrap();
+        for (route, length) in [
+            (
+                grid.shortest_route().unwrap(),
+                grid.shortest_path().unwrap(),
+            ),
+            (
+                grid.shortest_route_any_start().unwrap(),
+                grid.shortest_paths_any_start().unwrap(),
+            ),
+        ] {
+            assert_eq!(route.len() - 1, length);
+            assert_eq!(*route.last().unwrap(), grid.end_coordinate);
+            for (from, to) in route.iter().zip(route.iter().skip(1)) {
+                let from_index = grid.graph_indices[from];
+                let to_index = grid.graph_indices[to];
+                assert!(grid.graph.contains_edge(from_index, to_index));
+            }
+            let rendered = grid.render_route(&route);
+            assert_eq!(
+                rendered.chars().filter(|c| !".E\n".contains(*c)).count(),
+                length
+            );
+            assert_eq!(rendered.lines().count(), 5);
+        }
+        assert_eq!(grid.shortest_route().unwrap()[0], grid.start_coordinate);
+        let (x, y) = grid.shortest_route_any_start().unwrap()[0];
+        assert_eq!(grid.cells[y][x], 0);
+    }
+
+    #[test]
+    fn test_diagonal() {
+        let orthogonal = SAMPLE.parse::<Grid>().unwrap();
+        let grid = Grid::parse_with(SAMPLE, true).unwrap();
+        assert_eq!(grid.shortest_path().unwrap(), 27);
+        assert!(grid.shortest_path().unwrap() <= orthogonal.shortest_path().unwrap());
+        assert_eq!(grid.shortest_paths_any_start().unwrap(), 26);
+        assert!(
+            grid.shortest_paths_any_start().unwrap()
+                <= orthogonal.shortest_paths_any_start().unwrap()
+        );
+        for route in [
+            grid.shortest_route().unwrap(),
+            grid.shortest_route_any_start().unwrap(),
+        ] {
+            assert_eq!(*route.last().unwrap(), grid.end_coordinate);
+            let (x, y) = route[0];
+            assert_eq!(grid.cells[y][x], 0);
+            for (from, to) in route.iter().zip(route.iter().skip(1)) {
+                assert!(from.0.abs_diff(to.0) <= 1 && from.1.abs_diff(to.1) <= 1);
+                assert!(grid.cells[to.1][to.0] <= grid.cells[from.1][from.0] + 1);
+            }
+        }
+        assert_eq!(grid.shortest_route().unwrap()[0], grid.start_coordinate);
+    }
+
+    #[test]
+    fn test_unreachable_end() {
+        // nothing can climb from a to c
+        let grid = "SacE\nSacz\n".parse::<Grid>().unwrap();
+        assert_eq!(grid.shortest_path(), None);
+        assert_eq!(grid.shortest_paths_any_start(), None);
+        assert_eq!(grid.shortest_route(), None);
+        assert_eq!(grid.shortest_route_any_start(), None);
+    }
+}
@@ -1,5 +1,5 @@
 use clap::{Parser, ValueEnum};
-use std::cmp::{Ordering, PartialOrd};
+use std::cmp::Ordering;
 use std::fmt::{Display, Formatter};
 
 use itertools::{EitherOrBoth, Itertools};
@@ -11,6 +11,7 @@ use nom::{
     sequence::{delimited, pair, terminated},
     IResult,
 };
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Unexpected, Visitor};
 
 #[derive(ValueEnum, Debug, PartialEq, Eq, Clone, Copy)]
 enum Mode {
@@ -25,14 +26,29 @@ struct Args {
     mode: Mode,
     #[clap(short, long)]
     verbose: bool,
+    /// Parse packets as JSON instead of with the hand-written parser
+    #[clap(long)]
+    json: bool,
+    #[command(flatten)]
+    common: aoclib::CommonArgs,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 enum Packet {
     Number(i32),
     List(Vec<Packet>),
 }
 
+impl Packet {
+    /// How deeply lists are nested in this packet; a bare number has depth 0
+    fn depth(&self) -> usize {
+        match self {
+            Self::Number(_) => 0,
+            Self::List(items) => 1 + items.iter().map(|i| i.depth()).max().unwrap_or(0),
+        }
+    }
+}
+
 impl Display for Packet {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -52,33 +68,78 @@ impl Display for Packet {
     }
 }
 
+/// Equality follows the puzzle's ordering, so `1 == [1]`
+impl PartialEq for Packet {
+    fn eq(&self, other: &Packet) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Packet {}
+
 impl PartialOrd for Packet {
     fn partial_cmp(&self, other: &Packet) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The puzzle's ordering, where a bare number compares like a list holding just that number
+impl Ord for Packet {
+    fn cmp(&self, other: &Packet) -> Ordering {
         match (self, other) {
-            (Packet::Number(lhs), Packet::Number(rhs)) => Some(lhs.cmp(rhs)),
+            (Packet::Number(lhs), Packet::Number(rhs)) => lhs.cmp(rhs),
             (Packet::List(lhs), Packet::List(rhs)) => {
                 for item in lhs.iter().zip_longest(rhs.iter()) {
                     match item {
-                        EitherOrBoth::Left(_) => return Some(Ordering::Greater),
-                        EitherOrBoth::Right(_) => return Some(Ordering::Less),
-                        EitherOrBoth::Both(l, r) => match l.partial_cmp(r) {
-                            s @ Some(Ordering::Less) => return s,
-                            s @ Some(Ordering::Greater) => return s,
-                            _ => {}
+                        EitherOrBoth::Left(_) => return Ordering::Greater,
+                        EitherOrBoth::Right(_) => return Ordering::Less,
+                        EitherOrBoth::Both(l, r) => match l.cmp(r) {
+                            Ordering::Equal => {}
+                            s => return s,
                         },
                     }
                 }
-                Some(Ordering::Equal)
+                Ordering::Equal
             }
-            (lhs, rhs @ Packet::List(_)) => Packet::List(vec![lhs.clone()]).partial_cmp(rhs),
-            (lhs @ Packet::List(_), rhs) => lhs.partial_cmp(&Packet::List(vec![rhs.clone()])),
+            (lhs, rhs @ Packet::List(_)) => Packet::List(vec![lhs.clone()]).cmp(rhs),
+            (lhs @ Packet::List(_), rhs) => lhs.cmp(&Packet::List(vec![rhs.clone()])),
         }
     }
 }
 
-impl Ord for Packet {
-    fn cmp(&self, other: &Packet) -> Ordering {
-        self.partial_cmp(other).unwrap()
+impl<'de> Deserialize<'de> for Packet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct PacketVisitor;
+
+        impl<'de> Visitor<'de> for PacketVisitor {
+            type Value = Packet;
+
+            fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+                write!(f, "an integer or a list of packets")
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Packet, E> {
+                i32::try_from(v)
+                    .map(Packet::Number)
+                    .map_err(|_| E::invalid_value(Unexpected::Signed(v), &self))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Packet, E> {
+                i32::try_from(v)
+                    .map(Packet::Number)
+                    .map_err(|_| E::invalid_value(Unexpected::Unsigned(v), &self))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Packet, A::Error> {
+                let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(item) = seq.next_element()? {
+                    items.push(item);
+                }
+                Ok(Packet::List(items))
+            }
+        }
+
+        deserializer.deserialize_any(PacketVisitor)
     }
 }
 
@@ -117,39 +178,88 @@ fn parse_all_packets(s: &str) -> anyhow::Result<Vec<Packet>> {
     Ok(packets)
 }
 
-fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
-    let stdin = std::io::stdin();
-    let input = std::io::read_to_string(stdin)?;
-    if args.mode == Mode::Part1 {
-        let ok_indices = parse_packet_pairs(&input)?
+fn parse_packet_json(line: &str, line_number: usize) -> anyhow::Result<Packet> {
+    serde_json::from_str(line).map_err(|e| anyhow::anyhow!("line {}: {}", line_number + 1, e))
+}
+
+fn parse_packet_pairs_json(s: &str) -> anyhow::Result<Vec<(Packet, Packet)>> {
+    let lines = s.lines().enumerate().collect::<Vec<_>>();
+    lines
+        .split(|(_, line)| line.trim().is_empty())
+        .filter(|group| !group.is_empty())
+        .map(|group| match group {
+            [(lhs_number, lhs), (rhs_number, rhs)] => Ok((
+                parse_packet_json(lhs, *lhs_number)?,
+                parse_packet_json(rhs, *rhs_number)?,
+            )),
+            _ => anyhow::bail!(
+                "line {}: expected a pair of packets, got {}",
+                group[0].0 + 1,
+                group.len()
+            ),
+        })
+        .collect()
+}
+
+fn parse_all_packets_json(s: &str) -> anyhow::Result<Vec<Packet>> {
+    s.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| parse_packet_json(line, i))
+        .collect()
+}
+
+fn solve(input: &str, mode: Mode, verbose: bool, json: bool) -> anyhow::Result<usize> {
+    if mode == Mode::Part1 {
+        let pairs = if json {
+            parse_packet_pairs_json(input)?
+        } else {
+            parse_packet_pairs(input)?
+        };
+        if verbose {
+            let max_depth = pairs
+                .iter()
+                .flat_map(|(lhs, rhs)| [lhs.depth(), rhs.depth()])
+                .max()
+                .unwrap_or(0);
+            println!("max depth: {}", max_depth);
+        }
+        let ok_indices = pairs
             .into_iter()
             .enumerate()
             .filter_map(|(i, (lhs, rhs))| {
                 if lhs <= rhs {
-                    if args.verbose {
+                    if verbose {
                         println!("OK:  {} ⇐ {}", lhs, rhs);
                     }
                     Some(i + 1)
                 } else {
-                    if args.verbose {
+                    if verbose {
                         println!("BAD: {} > {}", lhs, rhs);
                     }
                     None
                 }
             })
             .sum::<usize>();
-        println!("{}", ok_indices);
+        Ok(ok_indices)
     } else {
         let delimiters = &[
             Packet::List(vec![Packet::List(vec![Packet::Number(2)])]),
             Packet::List(vec![Packet::List(vec![Packet::Number(6)])]),
         ];
-        let mut all_packets = parse_all_packets(&input)?;
+        let mut all_packets = if json {
+            parse_all_packets_json(input)?
+        } else {
+            parse_all_packets(input)?
+        };
+        if verbose {
+            let max_depth = all_packets.iter().map(|p| p.depth()).max().unwrap_or(0);
+            println!("max depth: {}", max_depth);
+        }
         all_packets.push(delimiters[0].clone());
         all_packets.push(delimiters[1].clone());
         all_packets.sort();
-        if args.verbose {
+        if verbose {
             for packet in &all_packets {
                 println!("{}", packet);
             }
@@ -158,7 +268,133 @@ fn main() -> anyhow::Result<()> {
             .iter()
             .map(|d| all_packets.iter().position(|i| i == d).unwrap() + 1)
             .product::<usize>();
-        println!("{}", decoder_key);
+        Ok(decoder_key)
     }
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let input = args.common.read_input()?;
+    let _timer = args.common.start_timer();
+    println!("{}", solve(&input, args.mode, args.verbose, args.json)?);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        parse_all_packets, parse_all_packets_json, parse_packet, parse_packet_pairs,
+        parse_packet_pairs_json, solve, Mode, Packet,
+    };
+    use std::cmp::Ordering;
+
+    const SAMPLE: &str = "[1,1,3,1,1]
+[1,1,5,1,1]
+
+[[1],[2,3,4]]
+[[1],4]
+
+[9]
+[[8,7,6]]
+
+[[4,4],4,4]
+[[4,4],4,4,4]
+
+[7,7,7,7]
+[7,7,7]
+
+[]
+[3]
+
+[[[]]]
+[[]]
+
+[1,[2,[3,[4,[5,6,7]]]],8,9]
+[1,[2,[3,[4,[5,6,0]]]],8,9]
+";
+
+    #[test]
+    fn test_solve_sample() {
+        assert_eq!(solve(SAMPLE, Mode::Part1, false, false).unwrap(), 13);
+        assert_eq!(solve(SAMPLE, Mode::Part2, false, false).unwrap(), 140);
+    }
+
+    #[test]
+    fn test_depth() {
+        let depth = |s| parse_packet(s).unwrap().1.depth();
+        assert_eq!(Packet::Number(4).depth(), 0);
+        assert_eq!(depth("[]"), 1);
+        assert_eq!(depth("[1,1,3,1,1]"), 1);
+        assert_eq!(depth("[[1],[2,3,4]]"), 2);
+        assert_eq!(depth("[[[]]]"), 3);
+        assert_eq!(depth("[1,[2,[3,[4,[5,6,7]]]],8,9]"), 5);
+    }
+
+    #[test]
+    fn test_max_depth_of_all_packets() {
+        let packets =
+            parse_all_packets("[[4,4],4,4]\n[[[]]]\n\n[1,[2,[3,[4,[5,6,0]]]],8,9]\n").unwrap();
+        assert_eq!(packets.iter().map(|p| p.depth()).max(), Some(5));
+    }
+
+    #[test]
+    fn test_cmp_agrees_with_partial_cmp() {
+        let packet = |s| parse_packet(s).unwrap().1;
+        let mut pairs = parse_packet_pairs(SAMPLE).unwrap();
+        for (lhs, rhs) in [
+            ("1", "[1]"),
+            ("[1]", "[[1]]"),
+            ("[2]", "[[1],3]"),
+            ("[[]]", "[]"),
+            ("[[2,3]]", "[2,[3]]"),
+            ("5", "[[4]]"),
+            ("[]", "0"),
+        ] {
+            pairs.push((packet(lhs), packet(rhs)));
+        }
+        for (lhs, rhs) in pairs.iter() {
+            assert_eq!(Some(lhs.cmp(rhs)), lhs.partial_cmp(rhs), "{} {}", lhs, rhs);
+            assert_eq!(lhs.cmp(rhs), rhs.cmp(lhs).reverse(), "{} {}", lhs, rhs);
+            assert_eq!(lhs <= rhs, lhs.cmp(rhs) != Ordering::Greater);
+            assert_eq!(
+                lhs == rhs,
+                lhs.cmp(rhs) == Ordering::Equal,
+                "{} {}",
+                lhs,
+                rhs
+            );
+        }
+        assert_eq!(packet("1"), packet("[1]"));
+        assert_eq!(packet("[[1],2]"), packet("[1,[2]]"));
+        assert_ne!(packet("[1]"), packet("[1,1]"));
+        assert_eq!(packet("1").cmp(&packet("[1]")), Ordering::Equal);
+        assert_eq!(packet("[2]").cmp(&packet("[[1],3]")), Ordering::Greater);
+        assert_eq!(packet("[]").cmp(&packet("0")), Ordering::Less);
+    }
+
+    #[test]
+    fn test_json_matches_nom() {
+        assert_eq!(
+            parse_packet_pairs_json(SAMPLE).unwrap(),
+            parse_packet_pairs(SAMPLE).unwrap()
+        );
+        assert_eq!(
+            parse_all_packets_json(SAMPLE).unwrap(),
+            parse_all_packets(SAMPLE).unwrap()
+        );
+        assert_eq!(solve(SAMPLE, Mode::Part1, false, true).unwrap(), 13);
+        assert_eq!(solve(SAMPLE, Mode::Part2, false, true).unwrap(), 140);
+    }
+
+    #[test]
+    fn test_json_errors() {
+        assert_eq!(
+            parse_all_packets_json("[1]\n\n[\"a\"]\n")
+                .unwrap_err()
+                .to_string(),
+            "line 3: invalid type: string \"a\", expected an integer or a list of packets at line 1 column 4"
+        );
+        assert!(parse_all_packets_json("[3000000000]\n").is_err());
+        assert!(parse_packet_pairs_json("[1]\n[2]\n[3]\n").is_err());
+    }
+}
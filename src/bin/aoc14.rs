@@ -1,4 +1,12 @@
+use std::io::Write;
+use std::time::Duration;
+
 use clap::{Parser, ValueEnum};
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    execute,
+    terminal::{Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
 use itertools::Itertools;
 use nom::{
     bytes::complete::tag, character, combinator::map, multi::separated_list1,
@@ -16,12 +24,30 @@ enum Mode {
 #[derive(Debug, Parser)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    #[clap(short, long, value_enum)]
-    mode: Mode,
+    #[clap(short, long, value_enum, required_unless_present = "both")]
+    mode: Option<Mode>,
+    /// Print the part 1 count, then keep pouring into the same scene and print the part 2
+    /// count
+    #[clap(long, conflicts_with_all = ["mode", "runs", "animate"])]
+    both: bool,
     #[clap(short, long)]
     verbose: bool,
+    /// Pour the sand this many times, clearing it out in between, and report the average time
+    #[clap(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    runs: u64,
+    /// Redraw the scene every time a grain of sand comes to rest
+    #[clap(long, conflicts_with_all = ["runs", "verbose"])]
+    animate: bool,
+    /// How long to show each frame with --animate
+    #[clap(long, default_value_t = 32)]
+    ms_per_frame: u64,
+    #[command(flatten)]
+    common: aoclib::CommonArgs,
 }
 
+/// Where the sand pours in from
+const SOURCE: Point = Point::new(500, 0);
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum Cell {
     Empty,
@@ -51,17 +77,22 @@ impl Cell {
 
 #[derive(Debug)]
 struct Scene {
+    top_left: Point,
     grid: DenseGrid<Cell>,
     current_sand: Option<Point>,
     sand_created: usize,
+    /// Sand falling past this row is lost, even if there's something to catch it further down
+    abyss: Option<i64>,
 }
 
 impl Scene {
     fn new(top_left: Point, bottom_right: Point) -> Self {
         Scene {
+            top_left,
             grid: DenseGrid::new(top_left, bottom_right),
             current_sand: None,
             sand_created: 0,
+            abyss: None,
         }
     }
 
@@ -71,10 +102,53 @@ impl Scene {
         }
     }
 
+    /// Draw every path in `paths` (each a list of corners joined by straight lines) on top
+    /// of whatever is already in the scene
+    fn add_paths(&mut self, paths: &[Vec<Point>], of: Cell) {
+        for path in paths {
+            for (lhs, rhs) in path.iter().tuple_windows() {
+                self.add_path(*lhs, *rhs, of);
+            }
+        }
+    }
+
+    /// Clear out any sand so the scene can be simulated again, keeping the rock as it is
+    fn reset(&mut self) {
+        for cell in self.grid.values_mut() {
+            if *cell == Cell::Sand {
+                *cell = Cell::Empty;
+            }
+        }
+        self.current_sand = None;
+        self.sand_created = 0;
+    }
+
     fn dump(&self) {
         self.grid.dump_with(|c| c.as_char())
     }
 
+    /// Draw the scene with the source marked as `+`
+    fn render_to<W: Write>(&self, w: &mut W) -> std::io::Result<()> {
+        for dy in 0..self.grid.height() {
+            let y = self.top_left.y + dy as i64;
+            let line = self
+                .grid
+                .row(y)
+                .enumerate()
+                .map(|(dx, cell)| {
+                    let coordinate = Point::new(self.top_left.x + dx as i64, y);
+                    if coordinate == SOURCE && cell.is_empty() {
+                        '+'
+                    } else {
+                        cell.as_char()
+                    }
+                })
+                .collect::<String>();
+            writeln!(w, "{}", line)?;
+        }
+        Ok(())
+    }
+
     fn step(&mut self) -> bool {
         if let Some(coordinate) = self.current_sand.take() {
             let down = coordinate + Point { x: 0, y: 1 };
@@ -83,6 +157,7 @@ impl Scene {
             if !self.grid.contains(down)
                 || !self.grid.contains(down_left)
                 || !self.grid.contains(down_right)
+                || self.abyss.is_some_and(|abyss| down.y > abyss)
             {
                 self.sand_created -= 1;
                 return false;
@@ -96,9 +171,9 @@ impl Scene {
             } else {
                 self.grid[coordinate] = Cell::Sand;
             }
-        } else if self.grid[Point { x: 500, y: 0 }].is_empty() {
+        } else if self.grid[SOURCE].is_empty() {
             self.sand_created += 1;
-            self.current_sand = Some(Point { x: 500, y: 0 });
+            self.current_sand = Some(SOURCE);
         } else {
             return false;
         }
@@ -106,7 +181,20 @@ impl Scene {
     }
 
     fn simulate(&mut self) {
-        while self.step() {}
+        self.simulate_with(|_| {})
+    }
+
+    /// Like `simulate`, but calls `on_settle` each time a grain comes to rest
+    fn simulate_with<F: FnMut(&Scene)>(&mut self, mut on_settle: F) {
+        loop {
+            let falling = self.current_sand.is_some();
+            if !self.step() {
+                break;
+            }
+            if falling && self.current_sand.is_none() {
+                on_settle(self);
+            }
+        }
     }
 }
 
@@ -145,7 +233,7 @@ fn parse_scene(s: &str, mode: Mode) -> anyhow::Result<Scene> {
             .filter_map(|path| path.iter().map(|coordinate| coordinate.x).max())
             .max()
             .unwrap(),
-        500,
+        SOURCE.x,
     );
     let min_y = std::cmp::min(
         paths
@@ -160,49 +248,215 @@ fn parse_scene(s: &str, mode: Mode) -> anyhow::Result<Scene> {
         .filter_map(|path| path.iter().map(|coordinate| coordinate.y).max())
         .max()
         .unwrap();
-    let top_left = if mode == Mode::Part2 {
-        Point::new(min_x - 100, min_y)
-    } else {
-        Point::new(min_x, min_y)
-    };
-    let bottom_right = if mode == Mode::Part2 {
-        Point::new(max_x + 100, max_y + 2)
+    let (top_left, bottom_right) = if mode == Mode::Part2 {
+        // sand piles up in a triangle under the source, so it can't get further out than
+        // the floor is deep; one more column on each side lets the last grains check their
+        // diagonals
+        let floor_y = max_y + 2;
+        (
+            Point::new(std::cmp::min(min_x, SOURCE.x - floor_y), min_y),
+            Point::new(std::cmp::max(max_x, SOURCE.x + floor_y), floor_y),
+        )
     } else {
-        Point::new(max_x, max_y)
+        (Point::new(min_x, min_y), Point::new(max_x, max_y))
     };
     let mut scene = Scene::new(top_left, bottom_right);
-    for path in paths.into_iter() {
-        for (lhs, rhs) in path.into_iter().tuple_windows() {
-            scene.add_path(lhs, rhs, Cell::Rock);
-        }
-    }
+    scene.add_paths(&paths, Cell::Rock);
     if mode == Mode::Part2 {
         scene.add_path(
-            Point { x: 0, y: max_y + 2 },
-            Point {
-                x: 1000,
-                y: max_y + 2,
-            },
+            Point::new(top_left.x, bottom_right.y),
+            bottom_right,
             Cell::Rock,
         );
     }
     Ok(scene)
 }
 
-fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
-    let stdin = std::io::stdin();
-    let input = std::io::read_to_string(stdin)?;
-    let mut scene = parse_scene(&input, args.mode)?;
-    if args.verbose {
+/// Pour sand until it stops, returning how many units came to rest. With more than one run,
+/// the same scene gets cleared out and reused each time.
+fn solve(input: &str, mode: Mode, verbose: bool, runs: usize) -> anyhow::Result<usize> {
+    let mut scene = parse_scene(input, mode)?;
+    if verbose {
         println!("Before: ");
         scene.dump()
     }
-    scene.simulate();
-    println!("CREATED: {}", scene.sand_created);
-    if args.verbose {
+    let start = std::time::Instant::now();
+    for run in 0..runs {
+        if run > 0 {
+            scene.reset();
+        }
+        scene.simulate();
+    }
+    if runs > 1 {
+        println!(
+            "{} runs in {:?} ({:?} each)",
+            runs,
+            start.elapsed(),
+            start.elapsed() / runs as u32
+        );
+    }
+    if verbose {
         println!("After: ");
         scene.dump()
     }
+    Ok(scene.sand_created)
+}
+
+/// Find both answers with a single scene: pour into the part 2 scene until sand first falls
+/// past the lowest rock, which is the part 1 answer, then carry on until the source is blocked
+fn solve_both(input: &str) -> anyhow::Result<(usize, usize)> {
+    let mut scene = parse_scene(input, Mode::Part2)?;
+    // the floor is the bottom row of the grid, two below the lowest rock
+    let floor_y = scene.top_left.y + scene.grid.height() as i64 - 1;
+    scene.abyss = Some(floor_y - 2);
+    scene.simulate();
+    let part1 = scene.sand_created;
+    scene.abyss = None;
+    scene.simulate();
+    Ok((part1, scene.sand_created))
+}
+
+/// Pour sand, redrawing the scene in the alternate screen every time a grain comes to rest
+fn animate(input: &str, mode: Mode, delay: Duration) -> anyhow::Result<usize> {
+    let mut scene = parse_scene(input, mode)?;
+    let mut stdout = std::io::stdout();
+    execute!(stdout, EnterAlternateScreen, Clear(ClearType::All), Hide)?;
+    let mut result = Ok(());
+    scene.simulate_with(|scene| {
+        if result.is_ok() {
+            result = execute!(stdout, MoveTo(0, 0))
+                .and_then(|_| scene.render_to(&mut stdout))
+                .and_then(|_| stdout.flush());
+            std::thread::sleep(delay);
+        }
+    });
+    execute!(stdout, Show, LeaveAlternateScreen)?;
+    result?;
+    Ok(scene.sand_created)
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let input = args.common.read_input()?;
+    let _timer = args.common.start_timer();
+    let Some(mode) = args.mode else {
+        let (part1, part2) = solve_both(&input)?;
+        println!("CREATED: {}", part1);
+        println!("CREATED: {}", part2);
+        return Ok(());
+    };
+    let created = if args.animate {
+        animate(&input, mode, Duration::from_millis(args.ms_per_frame))?
+    } else {
+        solve(&input, mode, args.verbose, args.runs as usize)?
+    };
+    println!("CREATED: {}", created);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_scene, solve, solve_both, Cell, Mode, Point, Scene, SOURCE};
+
+    const SAMPLE: &str = "498,4 -> 498,6 -> 496,6
+503,4 -> 502,4 -> 502,9 -> 494,9
+";
+
+    #[test]
+    fn test_add_paths_overlays() {
+        let mut scene = Scene::new(Point::new(0, 0), Point::new(9, 9));
+        scene.add_paths(
+            &[vec![Point::new(0, 0), Point::new(0, 3), Point::new(2, 3)]],
+            Cell::Rock,
+        );
+        scene.add_paths(
+            &[
+                vec![Point::new(5, 5), Point::new(5, 7)],
+                vec![Point::new(1, 3), Point::new(1, 5)],
+            ],
+            Cell::Sand,
+        );
+        for p in [Point::new(0, 0), Point::new(0, 2), Point::new(2, 3)] {
+            assert_eq!(scene.grid[p], Cell::Rock);
+        }
+        for p in [Point::new(5, 6), Point::new(1, 3), Point::new(1, 5)] {
+            assert_eq!(scene.grid[p], Cell::Sand);
+        }
+        assert_eq!(scene.grid[Point::new(3, 3)], Cell::Empty);
+    }
+
+    #[test]
+    fn test_solve_sample() {
+        assert_eq!(solve(SAMPLE, Mode::Part1, false, 1).unwrap(), 24);
+        assert_eq!(solve(SAMPLE, Mode::Part2, false, 1).unwrap(), 93);
+    }
+
+    #[test]
+    fn test_reset() {
+        for (mode, expected) in [(Mode::Part1, 24), (Mode::Part2, 93)] {
+            let mut scene = parse_scene(SAMPLE, mode).unwrap();
+            scene.simulate();
+            assert_eq!(scene.sand_created, expected);
+            scene.reset();
+            assert_eq!(scene.sand_created, 0);
+            assert_eq!(scene.grid.extent(|c| *c == Cell::Sand), None);
+            scene.simulate();
+            assert_eq!(scene.sand_created, expected);
+            assert_eq!(solve(SAMPLE, mode, false, 3).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_part2_tight_bounds() {
+        let mut scene = parse_scene(SAMPLE, Mode::Part2).unwrap();
+        // the sample's lowest rock is at y=9, so the floor is at 11
+        assert_eq!(scene.grid.width(), 2 * 11 + 1);
+        assert_eq!(scene.grid.height(), 12);
+        scene.simulate();
+        assert_eq!(scene.sand_created, 93);
+        // it stopped because the source got blocked, not because sand fell off an edge
+        assert_eq!(scene.grid[SOURCE], Cell::Sand);
+        // and the pile never reached the outermost columns
+        assert_eq!(
+            scene.grid.extent(|c| *c == Cell::Sand),
+            Some((Point::new(490, 0), Point::new(510, 10)))
+        );
+    }
+
+    #[test]
+    fn test_simulate_with() {
+        for (mode, expected) in [(Mode::Part1, 24), (Mode::Part2, 93)] {
+            let mut scene = parse_scene(SAMPLE, mode).unwrap();
+            let mut settled = 0;
+            scene.simulate_with(|scene| {
+                settled += 1;
+                let mut out = vec![];
+                scene.render_to(&mut out).unwrap();
+                assert_eq!(out.iter().filter(|c| **c == b'o').count(), settled);
+            });
+            assert_eq!(settled, expected);
+            assert_eq!(scene.sand_created, expected);
+        }
+    }
+
+    #[test]
+    fn test_render_to() {
+        let mut scene = parse_scene(SAMPLE, Mode::Part1).unwrap();
+        let mut before = vec![];
+        scene.render_to(&mut before).unwrap();
+        let before = String::from_utf8(before).unwrap();
+        assert_eq!(before.lines().next(), Some("......+..."));
+        scene.simulate();
+        let mut after = vec![];
+        scene.render_to(&mut after).unwrap();
+        let after = String::from_utf8(after).unwrap();
+        assert_eq!(after.lines().nth(2), Some("......o..."));
+        assert_eq!(after.matches('o').count(), 24);
+        assert_eq!(after.matches('#').count(), before.matches('#').count());
+    }
+
+    #[test]
+    fn test_solve_both() {
+        assert_eq!(solve_both(SAMPLE).unwrap(), (24, 93));
+    }
+}
@@ -10,6 +10,7 @@ use nom::{
     IResult,
 };
 
+use aoclib::ranges::{coverage_len, merge_ranges};
 use aoclib::Point;
 
 #[derive(ValueEnum, Debug, PartialEq, Eq, Clone, Copy)]
@@ -25,8 +26,16 @@ struct Args {
     mode: Mode,
     #[clap(short, long)]
     verbose: bool,
+    /// Don't log anything; only print the answer
+    #[clap(short, long, conflicts_with = "verbose")]
+    quiet: bool,
     #[clap(short, long, value_parser)]
     param: i64,
+    /// In part 1, print the merged ranges covered on the target row before the count
+    #[clap(long)]
+    show_ranges: bool,
+    #[command(flatten)]
+    common: aoclib::CommonArgs,
 }
 
 #[derive(Debug)]
@@ -60,11 +69,6 @@ impl Sensor {
         }
     }
 
-    /// Find the number of points covered on a line at going from (x, -inf) to (x, inf)
-    pub fn projected_to_x(&self, x: i64) -> Option<RangeInclusive<i64>> {
-        self.projected_to(self.me.x, x, self.me.y)
-    }
-
     /// Find the number of points covered on a line at going from (-inf, y) to (inf, y)
     pub fn projected_to_y(&self, y: i64) -> Option<RangeInclusive<i64>> {
         self.projected_to(self.me.y, y, self.me.x)
@@ -103,36 +107,39 @@ fn parse_sensor_lines(s: &str) -> anyhow::Result<Vec<Sensor>> {
     Ok(lines)
 }
 
-fn has_gap_in_ranges(i: &mut Vec<RangeInclusive<i64>>, min: i64, max: i64) -> bool {
-    merge_ranges(i);
-    i.iter()
-        .tuple_windows()
-        .any(|(lhs, rhs)| *lhs.end() > min && *rhs.start() > min && *lhs.end() < max)
+/// The only point in `0..=max` on both axes that no sensor can see. Since there's exactly one,
+/// it must sit just outside some sensor's range, so only the ring one step past each sensor's
+/// radius needs checking.
+fn find_distress_beacon(sensors: &[Sensor], max: i64) -> Option<Point> {
+    sensors
+        .iter()
+        .flat_map(|sensor| sensor.me.manhattan_ring(sensor.radius as i64 + 1))
+        .filter(|p| (0..=max).contains(&p.x) && (0..=max).contains(&p.y))
+        .find(|p| !sensors.iter().any(|s| s.occludes(*p)))
 }
 
-fn merge_ranges(r: &mut Vec<RangeInclusive<i64>>) {
-    if r.len() < 2 {
-        return;
-    }
-    r.sort_by_key(|r| *r.start());
-    let (mut current, mut current_index) = (r[0].clone(), 0);
-    for i in 1..r.len() {
-        let this = r[i].clone();
-        if *this.start() <= *current.end() + 1 {
-            current = *current.start()..=std::cmp::max(*this.end(), *current.end());
-            r[current_index] = current.clone();
-        } else {
-            current_index += 1;
-            current = this.clone();
-            r[current_index] = this.clone();
-        }
-    }
-    r.truncate(current_index + 1);
+fn tuning_frequency(point: Point) -> i64 {
+    point.x * 4000000 + point.y
+}
+
+/// The merged, sorted ranges of x coordinates on row `y` that some sensor can see
+fn covered_ranges(sensors: &[Sensor], y: i64) -> Vec<RangeInclusive<i64>> {
+    let mut covered_ranges = sensors
+        .iter()
+        .filter_map(|sensor| sensor.projected_to_y(y))
+        .collect::<Vec<_>>();
+    covered_ranges.sort_by_key(|r| *r.start());
+    log::debug!("covered before merging: {:?}", covered_ranges);
+    merge_ranges(&mut covered_ranges);
+    log::debug!("covered after merging: {:?}", covered_ranges);
+    covered_ranges
 }
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
-    let log_level = if args.verbose {
+    let log_level = if args.quiet {
+        log::LevelFilter::Off
+    } else if args.verbose {
         log::LevelFilter::Debug
     } else {
         log::LevelFilter::Info
@@ -142,20 +149,17 @@ fn main() -> anyhow::Result<()> {
         .format_timestamp_millis()
         .filter_level(log_level)
         .init();
-    let stdin = std::io::stdin();
-    let input = std::io::read_to_string(stdin)?;
-    let start = std::time::Instant::now();
+    let input = args.common.read_input()?;
+    let _timer = args.common.start_timer();
     log::debug!("parsing input");
     let lines = parse_sensor_lines(&input)?;
     if args.mode == Mode::Part1 {
-        let mut covered_ranges = lines
-            .iter()
-            .filter_map(|sensor| sensor.projected_to_y(args.param))
-            .collect::<Vec<_>>();
-        covered_ranges.sort_by_key(|r| *r.start());
-        log::debug!("covered before merging: {:?}", covered_ranges);
-        merge_ranges(&mut covered_ranges);
-        log::debug!("covered after merging: {:?}", covered_ranges);
+        let covered_ranges = covered_ranges(&lines, args.param);
+        if args.show_ranges {
+            for range in covered_ranges.iter() {
+                println!("{}..={}", range.start(), range.end());
+            }
+        }
         let beacons_in_range = lines
             .iter()
             .filter(|s| s.neighbor.y == args.param)
@@ -164,47 +168,80 @@ fn main() -> anyhow::Result<()> {
             .unique()
             .count() as u64;
         log::debug!("there are {} beacons on the line", beacons_in_range);
-        let covered = covered_ranges
-            .into_iter()
-            .map(|r| r.end().abs_diff(*r.start()) + 1)
-            .sum::<u64>()
-            - beacons_in_range;
-        println!("covered: {:?}", covered);
+        let covered = coverage_len(&covered_ranges) - beacons_in_range;
+        if args.quiet {
+            println!("{}", covered);
+        } else {
+            println!("covered: {:?}", covered);
+        }
     } else {
-        let min = 0;
-        let max = args.param;
-        let mut buf = Vec::with_capacity(lines.len());
-        log::debug!("scanning for potential x coordinates");
-        let non_covered_x = (min..=max)
-            .filter(|x| {
-                buf.clear();
-                buf.extend(lines.iter().filter_map(|sensor| sensor.projected_to_x(*x)));
-                has_gap_in_ranges(&mut buf, min, max)
-            })
-            .collect::<Vec<i64>>();
-        log::debug!("scanning for potential y coordinates");
-        let non_covered_y = (min..=max)
-            .filter(|y| {
-                buf.clear();
-                buf.extend(lines.iter().filter_map(|sensor| sensor.projected_to_y(*y)));
-                has_gap_in_ranges(&mut buf, min, max)
-            })
-            .collect::<Vec<i64>>();
-        log::debug!(
-            "found {} x coordinates and {} y coordinates",
-            non_covered_x.len(),
-            non_covered_y.len()
-        );
-        'outer: for x in non_covered_x {
-            for y in &non_covered_y {
-                let point = Point::new(x, *y);
-                if !lines.iter().any(|s| s.occludes(point)) {
-                    log::info!("Frequency {} at {}", point.x * args.param + point.y, point);
-                    break 'outer;
+        match find_distress_beacon(&lines, args.param) {
+            Some(point) => {
+                let frequency = tuning_frequency(point);
+                if args.quiet {
+                    println!("{}", frequency);
+                } else {
+                    log::info!("Frequency {} at {}", frequency, point);
                 }
             }
+            None => anyhow::bail!("no room for the distress beacon"),
         }
-        log::debug!("succeeded in {:?}", start.elapsed());
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        covered_ranges, find_distress_beacon, parse_sensor_lines, tuning_frequency, Point,
+    };
+
+    const SAMPLE: &str = "Sensor at x=2, y=18: closest beacon is at x=-2, y=15
+Sensor at x=9, y=16: closest beacon is at x=10, y=16
+Sensor at x=13, y=2: closest beacon is at x=15, y=3
+Sensor at x=12, y=14: closest beacon is at x=10, y=16
+Sensor at x=10, y=20: closest beacon is at x=10, y=16
+Sensor at x=14, y=17: closest beacon is at x=10, y=16
+Sensor at x=8, y=7: closest beacon is at x=2, y=10
+Sensor at x=2, y=0: closest beacon is at x=2, y=10
+Sensor at x=0, y=11: closest beacon is at x=2, y=10
+Sensor at x=20, y=14: closest beacon is at x=25, y=17
+Sensor at x=17, y=20: closest beacon is at x=21, y=22
+Sensor at x=16, y=7: closest beacon is at x=15, y=3
+Sensor at x=14, y=3: closest beacon is at x=15, y=3
+Sensor at x=20, y=1: closest beacon is at x=15, y=3
+";
+
+    #[test]
+    fn test_covered_ranges_sample() {
+        let sensors = parse_sensor_lines(SAMPLE).unwrap();
+        assert_eq!(covered_ranges(&sensors, 10), vec![-2..=24]);
+        assert_eq!(covered_ranges(&sensors, 11), vec![-3..=13, 15..=25]);
+    }
+
+    #[test]
+    fn test_distress_beacon_sample() {
+        let sensors = parse_sensor_lines(SAMPLE).unwrap();
+        let beacon = find_distress_beacon(&sensors, 20).unwrap();
+        assert_eq!(beacon, Point::new(14, 11));
+        assert_eq!(tuning_frequency(beacon), 56000011);
+    }
+
+    #[test]
+    fn test_distress_beacon_edge_cases() {
+        let sensor = |s| parse_sensor_lines(s).unwrap();
+        // a radius-1 diamond at the origin covers all of 0..=1 but the far corner
+        let one = sensor("Sensor at x=0, y=0: closest beacon is at x=1, y=0");
+        assert_eq!(find_distress_beacon(&one, 1), Some(Point::new(1, 1)));
+        // a radius-2 diamond covers all of it
+        let two = sensor("Sensor at x=0, y=0: closest beacon is at x=2, y=0");
+        assert_eq!(find_distress_beacon(&two, 1), None);
+        // three diamonds that leave only the far corner of 0..=2 uncovered
+        let three = sensor(
+            "Sensor at x=0, y=0: closest beacon is at x=0, y=2
+Sensor at x=1, y=3: closest beacon is at x=1, y=4
+Sensor at x=3, y=1: closest beacon is at x=4, y=1",
+        );
+        assert_eq!(find_distress_beacon(&three, 2), Some(Point::new(2, 2)));
+    }
+}
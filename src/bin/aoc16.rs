@@ -1,5 +1,7 @@
 use std::cmp::max;
 use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
 
 use bit_set::BitSet;
 use clap::{Parser, ValueEnum};
@@ -13,6 +15,7 @@ use nom::{
 };
 use petgraph::algo::floyd_warshall::floyd_warshall;
 use petgraph::graph::{DiGraph, NodeIndex};
+use rayon::prelude::*;
 
 #[derive(ValueEnum, Debug, PartialEq, Eq, Clone, Copy)]
 enum Mode {
@@ -27,13 +30,23 @@ struct Args {
     mode: Mode,
     #[clap(short, long)]
     verbose: bool,
+    /// Don't log anything; only print the answer
+    #[clap(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Give up if stdin is a terminal and no input shows up within this many seconds
+    #[clap(long)]
+    stdin_timeout: Option<u64>,
+    /// In part 2, check valve subset pairings on every core (not with --verbose)
+    #[clap(long)]
+    parallel: bool,
+    #[command(flatten)]
+    common: aoclib::CommonArgs,
 }
 
 #[derive(PartialOrd, Ord, PartialEq, Eq, Clone, Copy, Hash)]
 struct ValveName([u8; 2]);
 
 impl ValveName {
-    #[allow(dead_code)]
     fn ordinal(&self) -> usize {
         (self.0[0] - b'A') as usize * 26 + (self.0[1] - b'A') as usize
     }
@@ -145,13 +158,7 @@ impl Scene {
         })
     }
 
-    fn find_best_rec(
-        &self,
-        state: State,
-        context: &mut Context,
-        is_part2: bool,
-        depth: usize,
-    ) -> u64 {
+    fn find_best_rec(&self, state: State, context: &mut Context, depth: usize) -> u64 {
         let prefix = " ".repeat(depth);
         log::debug!(
             "{0} at {1} [t={2}, d={3}]",
@@ -163,17 +170,7 @@ impl Scene {
         if let Some(v) = context.memo.get(&state) {
             return *v;
         }
-        let value = if state.remaining == 0 {
-            if is_part2 {
-                let mut new_state = state.clone();
-                new_state.remaining = 26;
-                new_state.position = ValveName::try_from("AA").unwrap();
-                new_state.is_part2 = false;
-                self.find_best_rec(new_state, context, false, depth + 1)
-            } else {
-                0
-            }
-        } else if context.is_done(&state) {
+        let value = if state.remaining == 0 || context.is_done(&state) {
             0
         } else {
             let mut res = 0;
@@ -186,7 +183,7 @@ impl Scene {
                     log::debug!("{0} opening {1}", prefix, state.position);
                     res = max(
                         res,
-                        this_contribution + self.find_best_rec(next, context, is_part2, depth + 1),
+                        this_contribution + self.find_best_rec(next, context, depth + 1),
                     );
                 }
             }
@@ -202,7 +199,7 @@ impl Scene {
                     let mut next = state.clone();
                     next.position = item;
                     next.remaining -= distance;
-                    res = max(res, self.find_best_rec(next, context, is_part2, depth + 1))
+                    res = max(res, self.find_best_rec(next, context, depth + 1))
                 }
             }
             res
@@ -211,8 +208,7 @@ impl Scene {
         value
     }
 
-    fn find_best(&self, is_part2: bool) -> u64 {
-        let start = ValveName::try_from("AA").unwrap();
+    fn build_context(&self) -> Context {
         let mut useful_valves = self
             .openable_valves
             .iter()
@@ -220,29 +216,196 @@ impl Scene {
             .map(|s| s.0)
             .cloned()
             .collect::<Vec<_>>();
-        useful_valves.push(start);
-        let mut context = Context::build(&self.graph, useful_valves);
-        let state = State::initial(start, is_part2);
-        self.find_best_rec(state, &mut context, is_part2, 0)
+        useful_valves.push(start_valve());
+        Context::build(&self.graph, useful_valves)
+    }
+
+    fn find_best(&self) -> u64 {
+        self.find_best_schedule(30, None).0
+    }
+
+    /// The best score for one actor with `minutes` to work with, optionally only allowed to
+    /// open the valves in `allowed`, along with the schedule that gets it
+    fn find_best_schedule(&self, minutes: u32, allowed: Option<&BitSet>) -> (u64, Schedule) {
+        let mut context = self.build_context();
+        let mut state = State::initial(start_valve(), minutes);
+        if let Some(allowed) = allowed {
+            // pretend everything else is already open so nobody bothers with it
+            for valve in self.openable_valves.keys() {
+                if !allowed.contains(valve.ordinal()) {
+                    state.open(valve);
+                }
+            }
+        }
+        let best = self.find_best_rec(state.clone(), &mut context, 0);
+        (best, self.schedule(&context, state, minutes))
+    }
+
+    /// Retrace the choices that `find_best_rec` made from `state` using its memo
+    fn schedule(&self, context: &Context, mut state: State, minutes: u32) -> Schedule {
+        let mut schedule = vec![];
+        while let Some(&value) = context.memo.get(&state) {
+            if value == 0 {
+                break;
+            }
+            if let Some(flow_rate) = self.openable_valves.get(&state.position) {
+                if state.can_open(&state.position) {
+                    let contribution = (state.remaining - 1) as u64 * flow_rate;
+                    let mut next = state.next();
+                    next.open(&state.position);
+                    if context.memo.get(&next).map(|v| v + contribution) == Some(value) {
+                        schedule.push((state.position, minutes - state.remaining + 1));
+                        state = next;
+                        continue;
+                    }
+                }
+            }
+            let moved = context
+                .useful_valves
+                .iter()
+                .filter(|item| **item != state.position && state.can_open(item))
+                .filter_map(|item| {
+                    let distance = context.distances[&(state.position, *item)];
+                    (distance <= state.remaining).then(|| {
+                        let mut next = state.clone();
+                        next.position = *item;
+                        next.remaining -= distance;
+                        next
+                    })
+                })
+                .find(|next| context.memo.get(next) == Some(&value));
+            match moved {
+                Some(next) => state = next,
+                None => break,
+            }
+        }
+        schedule
+    }
+
+    /// How much pressure following `schedule` releases in `minutes`
+    fn schedule_total(&self, schedule: &Schedule, minutes: u32) -> u64 {
+        schedule
+            .iter()
+            .map(|(valve, minute)| self.openable_valves[valve] * (minutes - minute) as u64)
+            .sum()
+    }
+
+    fn print_schedule(&self, schedule: &Schedule, minutes: u32) {
+        for (valve, minute) in schedule {
+            let flow_rate = self.openable_valves[valve];
+            println!(
+                "minute {:>2}: open {} ({} x {} = {})",
+                minute,
+                valve,
+                flow_rate,
+                minutes - minute,
+                flow_rate * (minutes - minute) as u64
+            );
+        }
+        println!("total: {}", self.schedule_total(schedule, minutes));
+    }
+
+    /// The most pressure a single actor can release in `minutes`, for every set of valves
+    /// that it could end up opening in that time
+    fn best_by_subset(&self, context: &Context, minutes: u32) -> HashMap<BitSet, u64> {
+        let mut best = HashMap::new();
+        let mut work = vec![(start_valve(), minutes, BitSet::with_capacity(64), 0)];
+        while let Some((position, remaining, open_valves, released)) = work.pop() {
+            let entry = best.entry(open_valves.clone()).or_insert(0);
+            *entry = max(*entry, released);
+            for (item, flow_rate) in self.openable_valves.iter() {
+                if open_valves.contains(item.ordinal()) {
+                    continue;
+                }
+                // walk there and then spend a minute opening it
+                let cost = context.distances[&(position, *item)] + 1;
+                if cost < remaining {
+                    let mut next_open = open_valves.clone();
+                    next_open.insert(item.ordinal());
+                    let contribution = (remaining - cost) as u64 * flow_rate;
+                    work.push((*item, remaining - cost, next_open, released + contribution));
+                }
+            }
+        }
+        best
     }
+
+    /// Every valve subset a single actor can open in 26 minutes, best score first
+    fn subsets_by_score(&self) -> Vec<(BitSet, u64)> {
+        let context = self.build_context();
+        let best = self.best_by_subset(&context, 26);
+        log::debug!("{} reachable valve subsets", best.len());
+        let mut by_score = best.into_iter().collect::<Vec<_>>();
+        by_score.sort_unstable_by_key(|(_, score)| std::cmp::Reverse(*score));
+        by_score
+    }
+
+    /// Let you and the elephant each take 26 minutes, opening disjoint sets of valves
+    fn find_best_pair(&self) -> u64 {
+        self.find_best_pair_subsets().0
+    }
+
+    /// The best combined score, along with which valves you and the elephant each open
+    fn find_best_pair_subsets(&self) -> (u64, BitSet, BitSet) {
+        let by_score = self.subsets_by_score();
+        let top_score = by_score.first().map(|(_, score)| *score).unwrap_or(0);
+        let mut res = (0, BitSet::new(), BitSet::new());
+        for (mine, my_score) in by_score.iter() {
+            if my_score + top_score <= res.0 {
+                // everything after this scores even less
+                break;
+            }
+            // the first disjoint partner is the best one for this subset
+            if let Some((theirs, their_score)) =
+                by_score.iter().find(|(theirs, _)| mine.is_disjoint(theirs))
+            {
+                if my_score + their_score > res.0 {
+                    res = (my_score + their_score, mine.clone(), theirs.clone());
+                }
+            }
+        }
+        res
+    }
+
+    /// Same as `find_best_pair`, but checks the pairings on every core
+    fn find_best_pair_parallel(&self) -> u64 {
+        let by_score = self.subsets_by_score();
+        let top_score = by_score.first().map(|(_, score)| *score).unwrap_or(0);
+        let res = AtomicU64::new(0);
+        by_score.par_iter().for_each(|(mine, my_score)| {
+            if my_score + top_score <= res.load(Ordering::Relaxed) {
+                return;
+            }
+            if let Some((_, their_score)) =
+                by_score.iter().find(|(theirs, _)| mine.is_disjoint(theirs))
+            {
+                res.fetch_max(my_score + their_score, Ordering::Relaxed);
+            }
+        });
+        res.into_inner()
+    }
+}
+
+fn start_valve() -> ValveName {
+    ValveName::try_from("AA").unwrap()
 }
 
+/// Valves in the order they get opened, along with the minute each one gets opened in
+type Schedule = Vec<(ValveName, u32)>;
+
 #[derive(Debug, Hash, PartialEq, Eq, Clone)]
 struct State {
     open_valves: BitSet,
     position: ValveName,
     remaining: u32,
-    is_part2: bool,
 }
 
 impl State {
-    fn initial(start: ValveName, is_part2: bool) -> Self {
-        let remaining = if is_part2 { 26 } else { 30 };
+    fn initial(start: ValveName, remaining: u32) -> Self {
         State {
             open_valves: BitSet::with_capacity(64),
             position: start,
             remaining,
-            is_part2,
         }
     }
 
@@ -297,7 +460,9 @@ impl Context {
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
-    let log_level = if args.verbose {
+    let log_level = if args.quiet {
+        log::LevelFilter::Off
+    } else if args.verbose {
         log::LevelFilter::Debug
     } else {
         log::LevelFilter::Info
@@ -307,10 +472,89 @@ fn main() -> anyhow::Result<()> {
         .format_timestamp_millis()
         .filter_level(log_level)
         .init();
-    let stdin = std::io::stdin();
-    let input = std::io::read_to_string(stdin)?;
+    let input = args
+        .common
+        .read_input_with_timeout(args.stdin_timeout.map(Duration::from_secs))?;
+    let _timer = args.common.start_timer();
     let scene = Scene::parse(&input)?;
-    let best = scene.find_best(args.mode == Mode::Part2);
+    let best = match args.mode {
+        Mode::Part1 if args.verbose => {
+            let (best, schedule) = scene.find_best_schedule(30, None);
+            scene.print_schedule(&schedule, 30);
+            best
+        }
+        Mode::Part1 => scene.find_best(),
+        // printing the schedules needs to know who opens what, which only the serial search
+        // keeps track of
+        Mode::Part2 if args.verbose => {
+            let (best, mine, theirs) = scene.find_best_pair_subsets();
+            for (who, valves) in [("you", mine), ("elephant", theirs)] {
+                println!("{}:", who);
+                let (_, schedule) = scene.find_best_schedule(26, Some(&valves));
+                scene.print_schedule(&schedule, 26);
+            }
+            best
+        }
+        Mode::Part2 if args.parallel => scene.find_best_pair_parallel(),
+        Mode::Part2 => scene.find_best_pair(),
+    };
     println!("{:?}", best);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Scene, ValveName};
+
+    const SAMPLE: &str = "Valve AA has flow rate=0; tunnels lead to valves DD, II, BB
+Valve BB has flow rate=13; tunnels lead to valves CC, AA
+Valve CC has flow rate=2; tunnels lead to valves DD, BB
+Valve DD has flow rate=20; tunnels lead to valves CC, AA, EE
+Valve EE has flow rate=3; tunnels lead to valves FF, DD
+Valve FF has flow rate=0; tunnels lead to valves EE, GG
+Valve GG has flow rate=0; tunnels lead to valves FF, HH
+Valve HH has flow rate=22; tunnel leads to valve GG
+Valve II has flow rate=0; tunnels lead to valves AA, JJ
+Valve JJ has flow rate=21; tunnel leads to valve II
+";
+
+    #[test]
+    fn test_sample() {
+        let scene = Scene::parse(SAMPLE).unwrap();
+        assert_eq!(scene.find_best(), 1651);
+        assert_eq!(scene.find_best_pair(), 1707);
+        assert_eq!(scene.find_best_pair_parallel(), 1707);
+    }
+
+    #[test]
+    fn test_schedule_matches_total() {
+        let scene = Scene::parse(SAMPLE).unwrap();
+        let (best, schedule) = scene.find_best_schedule(30, None);
+        assert_eq!(best, 1651);
+        assert_eq!(scene.schedule_total(&schedule, 30), best);
+        let valve = |s| ValveName::try_from(s).unwrap();
+        assert_eq!(
+            schedule,
+            vec![
+                (valve("DD"), 2),
+                (valve("BB"), 5),
+                (valve("JJ"), 9),
+                (valve("HH"), 17),
+                (valve("EE"), 21),
+                (valve("CC"), 24),
+            ]
+        );
+
+        let (pair_best, mine, theirs) = scene.find_best_pair_subsets();
+        assert_eq!(pair_best, 1707);
+        let total = [mine, theirs]
+            .iter()
+            .map(|valves| {
+                let (best, schedule) = scene.find_best_schedule(26, Some(valves));
+                assert_eq!(scene.schedule_total(&schedule, 26), best);
+                best
+            })
+            .sum::<u64>();
+        assert_eq!(total, pair_best);
+    }
+}
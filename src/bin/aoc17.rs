@@ -1,11 +1,16 @@
 use std::cmp::min;
 use std::collections::HashMap;
+use std::io::Write;
+use std::time::Duration;
 
 use clap::Parser;
 use itertools::{EitherOrBoth, Itertools};
 
-const WIDTH: usize = 7;
+const DEFAULT_WIDTH: usize = 7;
+/// Shapes appear this many cells away from the left wall
+const LEFT_GAP: usize = 2;
 const TALLEST_SHAPE: usize = 4;
+const DUMP_WINDOW: usize = 30;
 
 #[derive(Debug, Parser)]
 #[command(author, version, about, long_about = None)]
@@ -16,8 +21,24 @@ struct Args {
     verbose: bool,
     #[clap(short, long)]
     print_raw: bool,
-    #[clap(short, long)]
+    /// Deprecated and hidden; cycle skipping is the default. Still accepted so existing
+    /// invocations keep working.
+    #[clap(short, long, hide = true, conflicts_with = "no_cycle_skip")]
     estimate_cycles: bool,
+    /// Simulate every rock instead of skipping ahead once the tower starts repeating
+    #[clap(long)]
+    no_cycle_skip: bool,
+    /// Print the top of the chamber every N rocks
+    #[clap(long)]
+    dump_every: Option<usize>,
+    /// How many cells wide the chamber is
+    #[clap(long, default_value_t = DEFAULT_WIDTH)]
+    width: usize,
+    /// Give up if stdin is a terminal and no input shows up within this many seconds
+    #[clap(long)]
+    stdin_timeout: Option<u64>,
+    #[command(flatten)]
+    common: aoclib::CommonArgs,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -36,14 +57,6 @@ impl Cell {
         }
     }
 
-    fn as_bits(&self) -> u16 {
-        match self {
-            Cell::Empty => 0b01,
-            Cell::Stuck => 0b10,
-            Cell::Moving => 0b11,
-        }
-    }
-
     fn frozen(&self) -> Self {
         match self {
             Self::Moving => Self::Stuck,
@@ -63,27 +76,26 @@ impl Cell {
 const E: Cell = Cell::Empty;
 const M: Cell = Cell::Moving;
 
-const SHAPES: &[&[[Cell; WIDTH]]] = &[
-    &[[E, E, M, M, M, M, E]],
-    &[
-        [E, E, E, M, E, E, E],
-        [E, E, M, M, M, E, E],
-        [E, E, E, M, E, E, E],
-    ],
-    &[
-        [E, E, E, E, M, E, E],
-        [E, E, E, E, M, E, E],
-        [E, E, M, M, M, E, E],
-    ],
-    &[
-        [E, E, M, E, E, E, E],
-        [E, E, M, E, E, E, E],
-        [E, E, M, E, E, E, E],
-        [E, E, M, E, E, E, E],
-    ],
-    &[[E, E, M, M, E, E, E], [E, E, M, M, E, E, E]],
+/// Each shape, top row first, trimmed to its own width; they get padded out to the width
+/// of the chamber when they're dropped in
+const SHAPES: &[&[&[Cell]]] = &[
+    &[&[M, M, M, M]],
+    &[&[E, M, E], &[M, M, M], &[E, M, E]],
+    &[&[E, E, M], &[E, E, M], &[M, M, M]],
+    &[&[M], &[M], &[M], &[M]],
+    &[&[M, M], &[M, M]],
 ];
 
+/// The narrowest chamber that every shape fits into when it first appears
+fn min_width() -> usize {
+    LEFT_GAP
+        + SHAPES
+            .iter()
+            .flat_map(|shape| shape.iter().map(|row| row.len()))
+            .max()
+            .unwrap_or(0)
+}
+
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 enum Motion {
     Left,
@@ -118,13 +130,13 @@ fn move_down(current: &mut [Cell], lower: &mut [Cell]) {
 
 fn move_dir(motion: Motion, row: &mut [Cell]) {
     if motion == Motion::Right {
-        for index in (0..WIDTH).rev() {
+        for index in (0..row.len()).rev() {
             if row[index] == Cell::Moving {
                 row.swap(index, index + 1)
             }
         }
     } else if motion == Motion::Left {
-        for index in 0..WIDTH {
+        for index in 0..row.len() {
             if row[index] == Cell::Moving {
                 row.swap(index, index - 1)
             }
@@ -134,19 +146,29 @@ fn move_dir(motion: Motion, row: &mut [Cell]) {
 
 #[derive(Debug)]
 struct Scene {
+    width: usize,
     floor_offset: usize,
-    rows: Vec<[Cell; WIDTH]>,
+    rows: Vec<Vec<Cell>>,
     next_shape: usize,
     next_motion: usize,
     next_tick: Tick,
     motions: Vec<Motion>,
     shape_bottom_row: Option<usize>,
     shapes_added: usize,
+    ticks: usize,
 }
 
 impl Scene {
-    fn new(motions: Vec<Motion>) -> Self {
-        Self {
+    fn new(motions: Vec<Motion>, width: usize) -> anyhow::Result<Self> {
+        if width < min_width() {
+            anyhow::bail!(
+                "chamber must be at least {} wide, not {}",
+                min_width(),
+                width
+            );
+        }
+        Ok(Self {
+            width,
             floor_offset: 0,
             next_shape: 0,
             shapes_added: 0,
@@ -155,22 +177,40 @@ impl Scene {
             next_tick: Tick::Lr,
             shape_bottom_row: None,
             rows: vec![],
-        }
+            ticks: 0,
+        })
+    }
+
+    /// How many rocks have started falling, including any skipped over by cycle detection
+    fn shapes_added(&self) -> usize {
+        self.shapes_added
+    }
+
+    /// How many times `tick` has been called; skipped cycles don't count
+    fn tick_count(&self) -> usize {
+        self.ticks
+    }
+
+    fn empty_row(&self) -> Vec<Cell> {
+        vec![Cell::Empty; self.width]
     }
 
-    fn empty_row() -> [Cell; WIDTH] {
-        [Cell::Empty; WIDTH]
+    /// Pad a row of a shape out to the full width of the chamber
+    fn shape_row(&self, cells: &[Cell]) -> Vec<Cell> {
+        let mut row = self.empty_row();
+        row[LEFT_GAP..LEFT_GAP + cells.len()].copy_from_slice(cells);
+        row
     }
 
     fn add_shape(&mut self) {
         let shape = SHAPES[self.next_shape];
         self.next_shape = (self.next_shape + 1) % SHAPES.len();
         for _ in 0..3 {
-            self.rows.push(Self::empty_row())
+            self.rows.push(self.empty_row())
         }
         let brow = self.rows.len();
         for row in shape.iter().rev() {
-            self.rows.push(*row);
+            self.rows.push(self.shape_row(row));
         }
         self.shapes_added += 1;
         self.shape_bottom_row = Some(brow);
@@ -180,7 +220,7 @@ impl Scene {
         if index >= self.rows.len() {
             return true;
         }
-        let row = self.rows[index];
+        let row = &self.rows[index];
         let res = match motion {
             Motion::Right => row
                 .iter()
@@ -210,7 +250,7 @@ impl Scene {
         } else if index == 0 {
             false
         } else {
-            (0..WIDTH).all(|x| {
+            (0..self.width).all(|x| {
                 if self.rows[index][x] == Cell::Moving {
                     let below = self.rows[index - 1][x];
                     index > 0 && below.can_move_into()
@@ -249,14 +289,16 @@ impl Scene {
     /// Freeze a shape in motion whose bottom edge is at `bottom_row`
     fn freeze(&mut self, bottom_row: usize) {
         for index in bottom_row..min(bottom_row + TALLEST_SHAPE, self.rows.len() - 1) {
-            self.rows[index] = self.rows[index].map(|c| c.frozen());
+            for cell in self.rows[index].iter_mut() {
+                *cell = cell.frozen();
+            }
         }
     }
 
     fn find_highest_occupied_row(&self) -> usize {
         let mut max = 0;
         for (i, row) in self.rows.iter().enumerate() {
-            if row.iter().any(|c| *c == Cell::Stuck) {
+            if row.contains(&Cell::Stuck) {
                 max = i
             }
         }
@@ -270,6 +312,7 @@ impl Scene {
 
     /// Run one iteration. Return a boolean indicating whether or not you did anything.
     fn tick(&mut self) -> bool {
+        self.ticks += 1;
         if let Some(bottom_row) = self.shape_bottom_row {
             let motion = match self.next_tick {
                 Tick::Lr => {
@@ -288,7 +331,6 @@ impl Scene {
                 self.freeze(bottom_row);
                 self.shape_bottom_row = None;
                 self.trim();
-            } else {
             }
             self.next_tick = self.next_tick.next();
             false
@@ -298,6 +340,53 @@ impl Scene {
         }
     }
 
+    /// The cells a falling rock could still reach, as (depth below the top of the tower,
+    /// column). Rocks only ever move down, left, and right, so every cell a rock passes
+    /// through is connected to the open air above the tower by such moves, and anything
+    /// outside this region can never affect the rest of the simulation.
+    fn reachable_region(&self) -> Vec<(usize, usize)> {
+        let top = self
+            .rows
+            .iter()
+            .rposition(|row| row.contains(&Cell::Stuck))
+            .map_or(0, |i| i + 1);
+        if top == 0 {
+            return vec![];
+        }
+        let open = |row: usize, x: usize| self.rows[row][x] != Cell::Stuck;
+        let mut seen = vec![vec![false; self.width]; top];
+        let mut queue = (0..self.width)
+            .filter(|x| open(top - 1, *x))
+            .map(|x| (top - 1, x))
+            .collect::<Vec<_>>();
+        for (row, x) in queue.iter() {
+            seen[*row][*x] = true;
+        }
+        let mut region = vec![];
+        while let Some((row, x)) = queue.pop() {
+            region.push((top - 1 - row, x));
+            let left = x.checked_sub(1).map(|x| (row, x));
+            let right = (x + 1 < self.width).then_some((row, x + 1));
+            let down = row.checked_sub(1).map(|row| (row, x));
+            for (row, x) in [left, right, down].into_iter().flatten() {
+                if open(row, x) && !seen[row][x] {
+                    seen[row][x] = true;
+                    queue.push((row, x));
+                }
+            }
+        }
+        region.sort_unstable();
+        region
+    }
+
+    fn cycle_key(&self) -> CycleKey {
+        CycleKey {
+            next_shape: self.next_shape,
+            next_motion: self.next_motion,
+            reachable: self.reachable_region(),
+        }
+    }
+
     fn find_last_full_row(&self) -> Option<usize> {
         let mut res = None;
         for (i, row) in self.rows.iter().enumerate() {
@@ -323,139 +412,337 @@ impl Scene {
     }
 
     fn draw(&self) {
-        for row in self.rows.iter().rev() {
-            println!("{}", row.iter().map(|r| r.as_char()).collect::<String>());
-        }
-        println!("-------");
-        println!("and then another {} rows", self.floor_offset);
+        let stdout = std::io::stdout();
+        self.draw_to(&mut stdout.lock(), self.rows.len())
+            .expect("failed to write to stdout");
     }
-}
-
-// each row can be encoded into 14 bits, but we'll just take the full 16
-#[derive(Debug)]
-#[repr(transparent)]
-struct CompactRow {
-    inner: u16,
-}
 
-impl CompactRow {
-    fn from_row(row: &[Cell; WIDTH]) -> Self {
-        Self {
-            inner: row[0].as_bits() << 12
-                | row[1].as_bits() << 10
-                | row[2].as_bits() << 8
-                | row[3].as_bits() << 6
-                | row[4].as_bits() << 4
-                | row[5].as_bits() << 2
-                | row[6].as_bits(),
+    /// Draw (at most) the top `window` rows of the chamber
+    fn draw_to<W: Write>(&self, w: &mut W, window: usize) -> std::io::Result<()> {
+        for row in self.rows.iter().rev().take(window) {
+            writeln!(w, "{}", row.iter().map(|r| r.as_char()).collect::<String>())?;
         }
+        writeln!(w, "{}", "-".repeat(self.width))?;
+        writeln!(
+            w,
+            "and then another {} rows",
+            self.floor_offset + self.rows.len().saturating_sub(window)
+        )
     }
 }
 
-const ROW_SIG: usize = 40;
-
-#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// Everything that determines how the rest of the simulation plays out, relative to the
+/// top of the tower: two states with equal keys grow the tower identically from then on
+#[derive(Debug, PartialEq, Eq, Hash)]
 struct CycleKey {
     next_shape: usize,
     next_motion: usize,
-    rows: [u16; ROW_SIG],
+    /// See [`Scene::reachable_region`]
+    reachable: Vec<(usize, usize)>,
 }
 
-fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
-    let log_level = if args.verbose {
-        log::LevelFilter::Debug
-    } else {
-        log::LevelFilter::Info
-    };
-    env_logger::builder()
-        .format_module_path(false)
-        .format_timestamp_millis()
-        .filter_level(log_level)
-        .init();
-    let stdin = std::io::stdin();
-    let input = std::io::read_to_string(stdin)?;
-    let motions = input
-        .trim()
+fn parse_motions(s: &str) -> anyhow::Result<Vec<Motion>> {
+    if s.trim().is_empty() {
+        anyhow::bail!("no input received");
+    }
+    s.trim()
         .chars()
         .map(|c| match c {
             '<' => Ok(Motion::Left),
             '>' => Ok(Motion::Right),
             _ => anyhow::bail!("what is {:?}", c),
         })
-        .collect::<anyhow::Result<Vec<Motion>>>()?;
-    let mut scene = Scene::new(motions);
-    let mut ticks = 0;
+        .collect()
+}
+
+/// Drop rocks until `args.stop_after` have landed, returning the height of the tower
+fn simulate<W: Write>(scene: &mut Scene, args: &Args, out: &mut W) -> std::io::Result<usize> {
     let mut cycles = HashMap::new();
-    let mut found_cycles = Vec::new();
-    let mut known_cycle = None;
-    let start = std::time::Instant::now();
-    while scene.shapes_added <= args.stop_after {
+    let mut skipped = false;
+    while scene.shapes_added() <= args.stop_after {
         if scene.tick() {
             let height = scene.find_highest_occupied_row() + scene.floor_offset;
             if args.print_raw {
-                println!("{}\t{}", scene.shapes_added, height);
+                writeln!(out, "{}\t{}", scene.shapes_added(), height)?;
             }
-            if args.estimate_cycles && known_cycle.is_none() && scene.rows.len() >= ROW_SIG {
-                let mut rows = [0u16; ROW_SIG];
-                for (i, r) in scene
-                    .rows
-                    .iter()
-                    .rev()
-                    .take(20)
-                    .map(CompactRow::from_row)
-                    .enumerate()
-                {
-                    rows[i] = r.inner;
+            if let Some(dump_every) = args.dump_every {
+                let landed = scene.shapes_added() - 1;
+                if landed > 0 && landed.is_multiple_of(dump_every) {
+                    writeln!(out, "=== After {} rocks ===", landed)?;
+                    scene.draw_to(out, DUMP_WINDOW)?;
                 }
-                let state = CycleKey {
-                    next_shape: scene.next_shape,
-                    next_motion: scene.next_motion,
-                    rows,
-                };
+            }
+            if !skipped && !args.no_cycle_skip {
+                let state = scene.cycle_key();
                 if let Some((last_sa, last_height)) = cycles.get(&state) {
+                    let shape_length = scene.shapes_added() - last_sa;
+                    let height_length = height - last_height;
                     log::debug!(
                         "repeat at {}..{} = {} height = {}",
                         last_sa,
-                        scene.shapes_added,
-                        scene.shapes_added - last_sa,
-                        height - last_height
+                        scene.shapes_added(),
+                        shape_length,
+                        height_length
                     );
-                    found_cycles.push((scene.shapes_added - last_sa, height - last_height))
-                }
-                cycles.insert(state, (scene.shapes_added, height));
-
-                if found_cycles.len() > 10
-                    && found_cycles
-                        .iter()
-                        .rev()
-                        .take(4)
-                        .tuple_windows()
-                        .all(|(l, r)| l == r)
-                {
-                    let (shape_length, height_length) = found_cycles.last().unwrap();
-
-                    let remaining = args.stop_after - scene.shapes_added;
+                    let remaining = args.stop_after.saturating_sub(scene.shapes_added());
                     let num_periods_to_skip = remaining / shape_length;
                     log::info!("skipping {:?} periods", num_periods_to_skip);
                     scene.shapes_added += num_periods_to_skip * shape_length;
                     scene.floor_offset += num_periods_to_skip * height_length;
-                    known_cycle = Some(true)
+                    skipped = true;
+                } else {
+                    cycles.insert(state, (scene.shapes_added(), height));
                 }
             }
         }
-        ticks += 1;
-        if ticks % 1000 == 0 {
+        if scene.tick_count().is_multiple_of(1000) {
             scene.check_drop_bottom();
         }
     }
+    Ok(scene.find_highest_occupied_row() + scene.floor_offset)
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let log_level = if args.verbose {
+        log::LevelFilter::Debug
+    } else {
+        log::LevelFilter::Info
+    };
+    env_logger::builder()
+        .format_module_path(false)
+        .format_timestamp_millis()
+        .filter_level(log_level)
+        .init();
+    let input = args
+        .common
+        .read_input_with_timeout(args.stdin_timeout.map(Duration::from_secs))?;
+    let _timer = args.common.start_timer();
+    let motions = parse_motions(&input)?;
+    let mut scene = Scene::new(motions, args.width)?;
+    let height = simulate(&mut scene, &args, &mut std::io::stdout().lock())?;
     if args.verbose {
         scene.draw();
+        log::debug!(
+            "{} rocks in {} ticks",
+            scene.shapes_added() - 1,
+            scene.tick_count()
+        );
     }
-    println!(
-        "{} (in {:?})",
-        scene.find_highest_occupied_row() + scene.floor_offset,
-        start.elapsed()
-    );
+    println!("{}", height);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{min_width, parse_motions, simulate, Args, Cell, Motion, Scene, DEFAULT_WIDTH};
+    use clap::{CommandFactory, Parser};
+
+    const SAMPLE: &str = ">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>";
+
+    fn default_args(stop_after: usize) -> Args {
+        Args {
+            stop_after,
+            verbose: false,
+            print_raw: false,
+            estimate_cycles: false,
+            no_cycle_skip: false,
+            dump_every: None,
+            width: DEFAULT_WIDTH,
+            stdin_timeout: None,
+            common: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_sample() {
+        let mut scene = Scene::new(parse_motions(SAMPLE).unwrap(), DEFAULT_WIDTH).unwrap();
+        let height = simulate(&mut scene, &default_args(2022), &mut std::io::sink()).unwrap();
+        assert_eq!(height, 3068);
+    }
+
+    #[test]
+    fn test_dump_every() {
+        let mut scene = Scene::new(parse_motions(SAMPLE).unwrap(), DEFAULT_WIDTH).unwrap();
+        let args = Args {
+            dump_every: Some(5),
+            ..default_args(22)
+        };
+        let mut out = Vec::new();
+        let height = simulate(&mut scene, &args, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out.matches("=== After").count(), 4);
+        assert!(out.contains("=== After 20 rocks ==="));
+        assert_eq!(out.matches("-------").count(), 4);
+
+        let mut scene = Scene::new(parse_motions(SAMPLE).unwrap(), DEFAULT_WIDTH).unwrap();
+        assert_eq!(
+            simulate(&mut scene, &default_args(22), &mut std::io::sink()).unwrap(),
+            height
+        );
+    }
+
+    #[test]
+    fn test_cycle_skip_matches_simulation() {
+        for stop_after in [100, 2022, 5000] {
+            let mut scene = Scene::new(parse_motions(SAMPLE).unwrap(), DEFAULT_WIDTH).unwrap();
+            let skipped = simulate(&mut scene, &default_args(stop_after), &mut std::io::sink());
+            let mut scene = Scene::new(parse_motions(SAMPLE).unwrap(), DEFAULT_WIDTH).unwrap();
+            let args = Args {
+                no_cycle_skip: true,
+                ..default_args(stop_after)
+            };
+            let simulated = simulate(&mut scene, &args, &mut std::io::sink());
+            assert_eq!(skipped.unwrap(), simulated.unwrap());
+        }
+    }
+
+    #[test]
+    fn test_sample_trillion() {
+        let mut scene = Scene::new(parse_motions(SAMPLE).unwrap(), DEFAULT_WIDTH).unwrap();
+        let height = simulate(
+            &mut scene,
+            &default_args(1000000000000),
+            &mut std::io::sink(),
+        )
+        .unwrap();
+        assert_eq!(height, 1514285714288);
+    }
+
+    #[test]
+    fn test_other_widths() {
+        for width in [min_width(), 9, 12] {
+            let mut scene = Scene::new(parse_motions(SAMPLE).unwrap(), width).unwrap();
+            let skipped = simulate(&mut scene, &default_args(2022), &mut std::io::sink());
+            let mut scene = Scene::new(parse_motions(SAMPLE).unwrap(), width).unwrap();
+            let args = Args {
+                no_cycle_skip: true,
+                ..default_args(2022)
+            };
+            let simulated = simulate(&mut scene, &args, &mut std::io::sink());
+            assert_eq!(skipped.unwrap(), simulated.unwrap());
+        }
+        assert!(Scene::new(parse_motions(SAMPLE).unwrap(), min_width() - 1).is_err());
+    }
+
+    #[test]
+    fn test_tick_count() {
+        let motions = parse_motions(SAMPLE).unwrap();
+        let mut scene = Scene::new(motions, DEFAULT_WIDTH).unwrap();
+        // the first rock is pushed by four jets before coming to rest: one tick to add it,
+        // then a push and a fall for each jet
+        while scene.shapes_added() < 2 {
+            scene.tick();
+        }
+        assert_eq!(scene.tick_count(), 1 + 4 * 2 + 1);
+        while scene.shapes_added() <= 10 {
+            scene.tick();
+        }
+        // ten rocks landed, plus adding the eleventh; that took 52 jets
+        assert_eq!(scene.shapes_added(), 11);
+        assert_eq!(scene.tick_count(), 11 + 52 * 2);
+    }
+
+    /// Build a scene from rows drawn top row first, using the same characters as `draw`
+    fn scene_from_rows(rows: &[&str]) -> Scene {
+        let mut scene = Scene::new(vec![], rows[0].len()).unwrap();
+        scene.rows = rows
+            .iter()
+            .rev()
+            .map(|row| {
+                row.chars()
+                    .map(|c| match c {
+                        '.' => Cell::Empty,
+                        '#' => Cell::Stuck,
+                        '@' => Cell::Moving,
+                        _ => panic!("unexpected cell {:?}", c),
+                    })
+                    .collect()
+            })
+            .collect();
+        scene
+    }
+
+    #[test]
+    fn test_can_move_into() {
+        assert!(Cell::Empty.can_move_into());
+        assert!(Cell::Moving.can_move_into());
+        assert!(!Cell::Stuck.can_move_into());
+    }
+
+    #[test]
+    fn test_can_move_laterally() {
+        let cases = [
+            // open on both sides
+            ("..@@...", true, true),
+            // blocked on the right by a rock
+            ("..@@#..", true, false),
+            // blocked on the left by a rock
+            (".#@@...", false, true),
+            // against the walls
+            ("@@.....", false, true),
+            (".....@@", true, false),
+            // a rock that isn't adjacent doesn't matter
+            ("#..@..#", true, true),
+        ];
+        for (row, left, right) in cases {
+            let scene = scene_from_rows(&[row]);
+            assert_eq!(scene.can_move_laterally(Motion::Left, 0), left, "{}", row);
+            assert_eq!(scene.can_move_laterally(Motion::Right, 0), right, "{}", row);
+        }
+        // rows above the top of the chamber are always clear
+        let scene = scene_from_rows(&["@@....."]);
+        assert!(scene.can_move_laterally(Motion::Left, 1));
+    }
+
+    #[test]
+    fn test_can_move_down() {
+        // the bottom row rests on the floor
+        let scene = scene_from_rows(&["..@@..."]);
+        assert!(!scene.can_move_down(0));
+
+        let scene = scene_from_rows(&["..@@...", "..@@...", "......."]);
+        // a shape can move into cells it currently occupies...
+        assert!(scene.can_move_down(2));
+        // ...and into empty space
+        assert!(scene.can_move_down(1));
+
+        // but not onto a rock, even under just one of its cells
+        let scene = scene_from_rows(&["..@@...", "...#..."]);
+        assert!(!scene.can_move_down(1));
+        let scene = scene_from_rows(&["..@@...", "#.....#"]);
+        assert!(scene.can_move_down(1));
+
+        // rows above the top of the chamber are always clear
+        assert!(scene.can_move_down(2));
+    }
+
+    #[test]
+    fn test_reachable_region_sees_under_overhangs() {
+        // the same height in every column, but only the first has room to slide a rock under
+        // the overhang into column 1
+        let open = scene_from_rows(&["###....", "#......", "#######"]);
+        let closed = scene_from_rows(&["###....", "##.....", "#######"]);
+        assert!(open.reachable_region().contains(&(1, 1)));
+        assert!(!closed.reachable_region().contains(&(1, 1)));
+        assert_ne!(open.reachable_region(), closed.reachable_region());
+        assert_eq!(
+            scene_from_rows(&["#######"]).reachable_region(),
+            Vec::<(usize, usize)>::new()
+        );
+    }
+
+    #[test]
+    fn test_estimate_cycles_flag() {
+        let args = Args::try_parse_from(["aoc17", "-s", "2022", "-e"]).unwrap();
+        assert!(args.estimate_cycles);
+        let mut scene = Scene::new(parse_motions(SAMPLE).unwrap(), DEFAULT_WIDTH).unwrap();
+        assert_eq!(
+            simulate(&mut scene, &args, &mut std::io::sink()).unwrap(),
+            3068
+        );
+        assert!(Args::try_parse_from(["aoc17", "-s", "1", "-e", "--no-cycle-skip"]).is_err());
+        let help = Args::command().render_help().to_string();
+        assert!(!help.contains("--estimate-cycles"));
+    }
+}
@@ -1,7 +1,10 @@
 use std::cmp::max;
-use std::collections::{HashSet, VecDeque};
+use std::collections::HashSet;
 use std::fmt::Debug;
+use std::io::Write;
+use std::time::Duration;
 
+use aoclib::{flood_fill, DenseGrid, Fill, Point};
 use clap::{Parser, ValueEnum};
 use itertools::{Itertools, MinMaxResult};
 
@@ -18,6 +21,20 @@ struct Args {
     verbose: bool,
     #[clap(short, long, value_enum)]
     mode: Mode,
+    /// In part 2, show a z-slice of the grid after each region is flood-filled (best
+    /// with small inputs)
+    #[clap(long)]
+    animate_fill: bool,
+    /// Which z-slice to show with --animate-fill
+    #[clap(long, default_value_t = 0)]
+    slice_z: i32,
+    #[clap(long, default_value_t = 100)]
+    ms_per_frame: u64,
+    /// Also report the volume of the air trapped inside the droplet
+    #[clap(long)]
+    volume: bool,
+    #[command(flatten)]
+    common: aoclib::CommonArgs,
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
@@ -44,17 +61,23 @@ impl Vec3 {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 enum Cell {
+    #[default]
     Unknown,
     Outside,
     AirBubble,
     Lava,
 }
 
-impl Default for Cell {
-    fn default() -> Self {
-        Cell::Unknown
+impl Cell {
+    fn as_char(&self) -> char {
+        match self {
+            Cell::Unknown => '?',
+            Cell::Outside => '.',
+            Cell::AirBubble => 'o',
+            Cell::Lava => '#',
+        }
     }
 }
 
@@ -93,6 +116,23 @@ impl<V: Debug + Default + Clone + Copy> Grid<V> {
         self.cells[idx] = v;
     }
 
+    /// A copy of one z-slice of the grid; cells outside the grid come back as the default
+    fn slice_z(&self, z: i32) -> DenseGrid<V> {
+        let mut slice = DenseGrid::new_with(
+            Point::new(0, 0),
+            Point::new(self.width as i64 - 1, self.height as i64 - 1),
+            V::default(),
+        );
+        if (0..self.depth).contains(&z) {
+            for x in 0..self.width {
+                for y in 0..self.height {
+                    slice.set(Point::new(x as i64, y as i64), self.get(Vec3::new(x, y, z)));
+                }
+            }
+        }
+        slice
+    }
+
     fn iter(&self) -> impl Iterator<Item = (Vec3, &V)> {
         let width = self.width;
         let height = self.height;
@@ -109,53 +149,33 @@ impl<V: Debug + Default + Clone + Copy> Grid<V> {
     }
 }
 
-fn flood_fill(g: &Grid<Cell>, start: Vec3) -> (Vec<Vec3>, bool) {
-    let mut connected = vec![];
-    let mut todo = VecDeque::new();
-    let mut seen = HashSet::new();
-    todo.push_back(start);
-    let mut is_empty = false;
-    while let Some(next) = todo.pop_front() {
-        connected.push(next);
-        for neighbor in next.neighbors() {
-            if neighbor.x < 0 || neighbor.y < 0 || neighbor.z < 0 {
-                is_empty = true;
-            } else if neighbor.x >= g.width || neighbor.y >= g.height || neighbor.z >= g.depth {
-                is_empty = true
-            } else {
-                match g.get(neighbor) {
-                    Cell::Lava => {}
-                    Cell::AirBubble => unreachable!(),
-                    Cell::Unknown => {
-                        if !seen.contains(&neighbor) {
-                            seen.insert(neighbor);
-                            todo.push_back(neighbor);
-                        }
-                    }
-                    Cell::Outside => is_empty = true,
-                }
-            }
+/// Fill the unknown region containing `start`, returning its cells and whether it reaches the
+/// outside
+fn fill_region(g: &Grid<Cell>, start: Vec3) -> (Vec<Vec3>, bool) {
+    flood_fill(start, Vec3::neighbors, |neighbor| {
+        let in_bounds = (0..g.width).contains(&neighbor.x)
+            && (0..g.height).contains(&neighbor.y)
+            && (0..g.depth).contains(&neighbor.z);
+        if !in_bounds {
+            return Fill::Boundary;
         }
-    }
-    (connected, is_empty)
+        match g.get(*neighbor) {
+            Cell::Lava => Fill::Blocked,
+            Cell::AirBubble => unreachable!(),
+            Cell::Unknown => Fill::Passable,
+            Cell::Outside => Fill::Boundary,
+        }
+    })
 }
 
-fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
-    let log_level = if args.verbose {
-        log::LevelFilter::Debug
-    } else {
-        log::LevelFilter::Info
-    };
-    env_logger::builder()
-        .format_module_path(false)
-        .format_timestamp_millis()
-        .filter_level(log_level)
-        .init();
-    let stdin = std::io::stdin();
-    let input = std::io::read_to_string(stdin)?;
-    let positions = input
-        .split('\n')
+/// How to show the flood fill while it runs
+struct Animation {
+    slice_z: i32,
+    delay: Duration,
+}
+
+fn parse_positions(s: &str) -> HashSet<Vec3> {
+    s.split('\n')
         .filter(|l| !l.trim().is_empty())
         .map(|line| {
             let mut fields = line.split(',').map(|v| v.parse::<i32>().unwrap());
@@ -165,75 +185,131 @@ fn main() -> anyhow::Result<()> {
                 z: fields.next().unwrap(),
             }
         })
-        .collect::<HashSet<Vec3>>();
-    let total_surface_area: usize = positions
+        .collect()
+}
+
+fn surface_area(cells: &HashSet<Vec3>) -> usize {
+    cells
         .iter()
         .map(|p| {
             p.neighbors()
                 .into_iter()
-                .filter(|n| !positions.contains(n))
+                .filter(|n| !cells.contains(n))
                 .count()
         })
-        .sum();
+        .sum()
+}
+
+fn build_grid(positions: &HashSet<Vec3>) -> Grid<Cell> {
+    let (min_x, max_x) = match positions.iter().map(|v| v.x).minmax() {
+        MinMaxResult::MinMax(a, b) => (a, b),
+        _ => panic!("unhandled x boundary"),
+    };
+    let (min_y, max_y) = match positions.iter().map(|v| v.y).minmax() {
+        MinMaxResult::MinMax(a, b) => (a, b),
+        _ => panic!("unhandled x boundary"),
+    };
+    let (min_z, max_z) = match positions.iter().map(|v| v.z).minmax() {
+        MinMaxResult::MinMax(a, b) => (a, b),
+        _ => panic!("unhandled x boundarz"),
+    };
+    let mut grid = Grid::<Cell>::new(
+        Vec3::new(min_x, min_y, min_z),
+        Vec3::new(max_x, max_y, max_z),
+    );
+    for item in positions {
+        grid.set(*item, Cell::Lava);
+    }
+    grid
+}
+
+/// Flood fill every unknown cell in the grid, marking it as either outside or part of an
+/// air bubble
+fn classify<W: Write>(
+    grid: &mut Grid<Cell>,
+    animation: Option<&Animation>,
+    out: &mut W,
+) -> std::io::Result<()> {
+    let mut region = 0;
+    // pick the first unknown cell and try to flood fill
+    loop {
+        let coord = grid
+            .iter()
+            .find(|(_, c)| **c == Cell::Unknown)
+            .map(|(v, _)| v);
+        if let Some(coord) = coord {
+            let (cells, state) = fill_region(grid, coord);
+            region += 1;
+            let size = cells.len();
+            for cell in cells {
+                grid.set(
+                    cell,
+                    if state {
+                        Cell::Outside
+                    } else {
+                        Cell::AirBubble
+                    },
+                )
+            }
+            if let Some(animation) = animation {
+                writeln!(
+                    out,
+                    "=== Region {} ({} cells, {}) ===",
+                    region,
+                    size,
+                    if state { "outside" } else { "air bubble" }
+                )?;
+                grid.slice_z(animation.slice_z)
+                    .dump_to(out, Cell::as_char)?;
+                std::thread::sleep(animation.delay);
+            }
+        } else {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// The air cells that `classify` found to be sealed off from the outside
+fn air_bubbles(grid: &Grid<Cell>) -> HashSet<Vec3> {
+    grid.iter()
+        .filter(|(_, c)| **c == Cell::AirBubble)
+        .map(|(v, _)| v)
+        .collect()
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let log_level = if args.verbose {
+        log::LevelFilter::Debug
+    } else {
+        log::LevelFilter::Info
+    };
+    env_logger::builder()
+        .format_module_path(false)
+        .format_timestamp_millis()
+        .filter_level(log_level)
+        .init();
+    let input = args.common.read_input()?;
+    let _timer = args.common.start_timer();
+    let positions = parse_positions(&input);
+    let total_surface_area = surface_area(&positions);
+    if args.mode == Mode::Part1 && !args.volume {
+        println!("exerior surface area: {}", total_surface_area);
+        return Ok(());
+    }
+    let mut grid = build_grid(&positions);
+    let animation = (args.mode == Mode::Part2 && args.animate_fill).then(|| Animation {
+        slice_z: args.slice_z,
+        delay: Duration::from_millis(args.ms_per_frame),
+    });
+    classify(&mut grid, animation.as_ref(), &mut std::io::stdout().lock())?;
+    let bubbles = air_bubbles(&grid);
     if args.mode == Mode::Part1 {
         println!("exerior surface area: {}", total_surface_area);
     } else {
-        let (min_x, max_x) = match positions.iter().map(|v| v.x).minmax() {
-            MinMaxResult::MinMax(a, b) => (a, b),
-            _ => panic!("unhandled x boundary"),
-        };
-        let (min_y, max_y) = match positions.iter().map(|v| v.y).minmax() {
-            MinMaxResult::MinMax(a, b) => (a, b),
-            _ => panic!("unhandled x boundary"),
-        };
-        let (min_z, max_z) = match positions.iter().map(|v| v.z).minmax() {
-            MinMaxResult::MinMax(a, b) => (a, b),
-            _ => panic!("unhandled x boundarz"),
-        };
-        let mut grid = Grid::<Cell>::new(
-            Vec3::new(min_x, min_y, min_z),
-            Vec3::new(max_x, max_y, max_z),
-        );
-        for item in positions {
-            grid.set(item, Cell::Lava);
-        }
-        // pick the first unknown cell and try to flood fill
-        loop {
-            let coord = grid
-                .iter()
-                .find(|(_, c)| **c == Cell::Unknown)
-                .map(|(v, _)| v);
-            if let Some(coord) = coord {
-                let (cells, state) = flood_fill(&grid, coord);
-                for cell in cells {
-                    grid.set(
-                        cell,
-                        if state {
-                            Cell::Outside
-                        } else {
-                            Cell::AirBubble
-                        },
-                    )
-                }
-            } else {
-                break;
-            }
-        }
         // compute the surface area of the air bubble(s)
-        let bubbles: HashSet<Vec3> = grid
-            .iter()
-            .filter(|(_, c)| **c == Cell::AirBubble)
-            .map(|(v, _)| v)
-            .collect();
-        let bubble_surface_area: usize = bubbles
-            .iter()
-            .map(|p| {
-                p.neighbors()
-                    .into_iter()
-                    .filter(|n| !bubbles.contains(n))
-                    .count()
-            })
-            .sum();
+        let bubble_surface_area = surface_area(&bubbles);
         println!(
             "{} - {} = {}",
             total_surface_area,
@@ -241,5 +317,80 @@ fn main() -> anyhow::Result<()> {
             total_surface_area - bubble_surface_area
         );
     }
+    if args.volume {
+        println!("trapped volume: {}", bubbles.len());
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        air_bubbles, build_grid, classify, parse_positions, surface_area, Animation, Cell, Vec3,
+    };
+    use std::time::Duration;
+
+    const SAMPLE: &str = "2,2,2
+1,2,2
+3,2,2
+2,1,2
+2,3,2
+2,2,1
+2,2,3
+2,2,4
+2,2,6
+1,2,5
+3,2,5
+2,1,5
+2,3,5
+";
+
+    fn cell_counts(animation: Option<&Animation>, out: &mut Vec<u8>) -> (usize, usize, usize) {
+        let mut grid = build_grid(&parse_positions(SAMPLE));
+        classify(&mut grid, animation, out).unwrap();
+        let count = |kind| grid.iter().filter(|(_, c)| **c == kind).count();
+        assert_eq!(count(Cell::Unknown), 0);
+        (
+            count(Cell::Lava),
+            count(Cell::Outside),
+            count(Cell::AirBubble),
+        )
+    }
+
+    #[test]
+    fn test_sample() {
+        let positions = parse_positions(SAMPLE);
+        assert_eq!(surface_area(&positions), 64);
+        let mut grid = build_grid(&positions);
+        classify(&mut grid, None, &mut std::io::sink()).unwrap();
+        let bubbles = air_bubbles(&grid);
+        assert_eq!(surface_area(&positions) - surface_area(&bubbles), 58);
+    }
+
+    #[test]
+    fn test_trapped_volume() {
+        let positions = parse_positions(SAMPLE);
+        let mut grid = build_grid(&positions);
+        classify(&mut grid, None, &mut std::io::sink()).unwrap();
+        let bubbles = air_bubbles(&grid);
+        assert_eq!(bubbles.len(), 1);
+        assert!(bubbles.contains(&Vec3::new(2, 2, 5)));
+        assert_eq!(surface_area(&positions) - surface_area(&bubbles), 58);
+    }
+
+    #[test]
+    fn test_animation_does_not_change_classification() {
+        let mut quiet = Vec::new();
+        let expected = cell_counts(None, &mut quiet);
+        assert!(quiet.is_empty());
+        let animation = Animation {
+            slice_z: 5,
+            delay: Duration::ZERO,
+        };
+        let mut animated = Vec::new();
+        assert_eq!(cell_counts(Some(&animation), &mut animated), expected);
+        let animated = String::from_utf8(animated).unwrap();
+        assert!(animated.contains("=== Region 1 "));
+        assert!(animated.contains('#'));
+    }
+}
@@ -8,6 +8,8 @@ use nom::{
     IResult,
 };
 use rayon::prelude::*;
+use std::collections::HashSet;
+use std::time::Duration;
 
 #[derive(ValueEnum, Debug, PartialEq, Eq, Clone, Copy)]
 enum Mode {
@@ -22,6 +24,17 @@ struct Args {
     verbose: bool,
     #[clap(short, long, value_enum)]
     mode: Mode,
+    /// Only log blueprints that crack at least this many geodes
+    #[clap(long)]
+    min_geodes: Option<u16>,
+    /// Allow building more of a robot than could ever be used (slow; for checking the caps)
+    #[clap(long)]
+    no_robot_caps: bool,
+    /// How many ore robots there are at the start (the puzzle says 1)
+    #[clap(long, default_value_t = 1)]
+    start_ore_robots: u16,
+    #[command(flatten)]
+    common: aoclib::CommonArgs,
 }
 
 #[derive(Debug)]
@@ -49,6 +62,23 @@ impl Blueprint {
     fn max_obsidian_use(&self) -> u16 {
         self.geode_cost.1
     }
+
+    /// Every robot has to cost something, or the robot caps stop meaning anything
+    fn validate(&self) -> anyhow::Result<()> {
+        for (name, cost) in [
+            ("ore robot ore", self.ore_cost),
+            ("clay robot ore", self.clay_cost),
+            ("obsidian robot ore", self.obsidian_cost.0),
+            ("obsidian robot clay", self.obsidian_cost.1),
+            ("geode robot ore", self.geode_cost.0),
+            ("geode robot obsidian", self.geode_cost.1),
+        ] {
+            if cost == 0 {
+                anyhow::bail!("blueprint {}: {} cost must be positive", self.id, name);
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Ord, PartialOrd)]
@@ -62,12 +92,17 @@ struct Inventory {
 }
 
 impl Inventory {
+    #[cfg(test)]
     fn new() -> Self {
+        Self::with_ore_robots(1)
+    }
+
+    fn with_ore_robots(ore_robots: u16) -> Self {
         Self {
             ore: 0,
             clay: 0,
             obsidian: 0,
-            ore_robots: 1,
+            ore_robots,
             clay_robots: 0,
             obsidian_robots: 0,
         }
@@ -80,6 +115,22 @@ impl Inventory {
         n.obsidian += self.obsidian_robots;
         n
     }
+
+    fn can_build_ore_robot(&self, blueprint: &Blueprint) -> bool {
+        self.ore >= blueprint.ore_cost
+    }
+
+    fn can_build_clay_robot(&self, blueprint: &Blueprint) -> bool {
+        self.ore >= blueprint.clay_cost
+    }
+
+    fn can_build_obsidian_robot(&self, blueprint: &Blueprint) -> bool {
+        self.ore >= blueprint.obsidian_cost.0 && self.clay >= blueprint.obsidian_cost.1
+    }
+
+    fn can_build_geode_robot(&self, blueprint: &Blueprint) -> bool {
+        self.ore >= blueprint.geode_cost.0 && self.obsidian >= blueprint.geode_cost.1
+    }
 }
 
 fn parse_blueprint(s: &str) -> IResult<&str, Blueprint> {
@@ -127,77 +178,138 @@ fn parse_blueprints(s: &str) -> anyhow::Result<Vec<Blueprint>> {
     if !res.trim().is_empty() {
         anyhow::bail!("unparsed input: {:?}", res);
     }
+    let mut ids = HashSet::new();
+    for bp in bps.iter() {
+        bp.validate()?;
+        if !ids.insert(bp.id) {
+            anyhow::bail!("duplicate blueprint id {}", bp.id);
+        }
+    }
     Ok(bps)
 }
 
-fn simulate_with(blueprint: &Blueprint, inventory: Inventory, ticks: u16) -> u16 {
-    let mut work = Vec::new();
-    let mut next_work = Vec::new();
+/// An upper bound on how many more geodes can be cracked from this point: assume ore and
+/// clay are free, so that an obsidian robot gets built every minute and a geode robot gets
+/// built every minute there's enough obsidian for one
+fn optimistic_geodes(inventory: &Inventory, blueprint: &Blueprint, remaining: u16) -> u16 {
+    let mut obsidian = inventory.obsidian;
+    let mut geodes = 0;
+    for (remaining, obsidian_robots) in (2..=remaining).rev().zip(inventory.obsidian_robots..) {
+        let build_geode = obsidian >= blueprint.geode_cost.1;
+        obsidian += obsidian_robots;
+        if build_geode {
+            obsidian -= blueprint.geode_cost.1;
+            geodes += remaining - 1;
+        }
+    }
+    geodes
+}
+
+/// Robots that could have been afforded during a minute in which nothing was built. There's
+/// no point building one of these next minute, since building it a minute earlier would have
+/// been strictly better.
+#[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq)]
+struct Declined {
+    ore: bool,
+    clay: bool,
+    obsidian: bool,
+    geode: bool,
+}
+
+/// Find the most geodes that can be cracked. With `robot_caps`, never build more of a robot
+/// than its resource could be spent each minute; turning that off is much slower but is a
+/// good check that the caps don't cost anything.
+fn simulate_with(blueprint: &Blueprint, inventory: Inventory, ticks: u16, robot_caps: bool) -> u16 {
+    // depth-first branch-and-bound; `geodes` already includes everything the geode robots
+    // built so far will ever crack, so it's a lower bound on the final score of the branch
+    let mut work = vec![(0, inventory, ticks, Declined::default())];
     let mut seen = LruCache::new(1000000);
     let mut best = 0;
-    let mut done = false;
-    work.push((0, inventory, ticks));
-    while !done {
-        while let Some((geodes, inventory, remaining_ticks)) = work.pop() {
-            best = std::cmp::max(best, geodes);
-            if remaining_ticks <= 1 {
-                done = true
-            }
-            if seen
-                .insert((geodes, inventory.clone(), remaining_ticks), ())
-                .is_some()
-            {
-                continue;
-            }
-            if inventory.ore >= blueprint.geode_cost.0
-                && inventory.obsidian >= blueprint.geode_cost.1
-            {
-                // greedy, probably unsafe optimization: always buy a geode robot if you can
-                // you could probably construct a parameter set where this fails but what
-                // are the odds that AoC did that?
-                let mut next = inventory.next();
-                next.ore -= blueprint.geode_cost.0;
-                next.obsidian -= blueprint.geode_cost.1;
-                let these_geodes = remaining_ticks - 1;
-                next_work.push((geodes + these_geodes, next, remaining_ticks - 1));
-            } else {
-                if inventory.ore >= blueprint.obsidian_cost.0
-                    && inventory.clay >= blueprint.obsidian_cost.1
-                    && inventory.obsidian_robots < blueprint.max_obsidian_use()
-                {
-                    let mut next = inventory.next();
-                    next.ore -= blueprint.obsidian_cost.0;
-                    next.clay -= blueprint.obsidian_cost.1;
-                    next.obsidian_robots += 1;
-                    next_work.push((geodes, next, remaining_ticks - 1))
-                }
-                if inventory.ore >= blueprint.ore_cost
-                    && inventory.ore_robots < blueprint.max_ore_use()
-                {
-                    let mut next = inventory.next();
-                    next.ore -= blueprint.ore_cost;
-                    next.ore_robots += 1;
-                    next_work.push((geodes, next, remaining_ticks - 1))
-                }
-                if inventory.ore >= blueprint.clay_cost
-                    && inventory.clay_robots < blueprint.max_clay_use()
-                {
-                    let mut next = inventory.next();
-                    next.ore -= blueprint.clay_cost;
-                    next.clay_robots += 1;
-                    next_work.push((geodes, next, remaining_ticks - 1))
-                }
-                next_work.push((geodes, inventory.next(), remaining_ticks - 1));
-            }
+    while let Some((geodes, inventory, remaining_ticks, declined)) = work.pop() {
+        best = std::cmp::max(best, geodes);
+        if remaining_ticks <= 1 {
+            continue;
+        }
+        // even the most optimistic build order can't beat what we've got
+        if geodes + optimistic_geodes(&inventory, blueprint, remaining_ticks) <= best {
+            continue;
+        }
+        if seen
+            .insert((geodes, inventory.clone(), remaining_ticks, declined), ())
+            .is_some()
+        {
+            continue;
+        }
+        // there's no point having more of a robot than you could spend its output each minute
+        let affordable = Declined {
+            ore: inventory.can_build_ore_robot(blueprint)
+                && (!robot_caps || inventory.ore_robots < blueprint.max_ore_use()),
+            clay: inventory.can_build_clay_robot(blueprint)
+                && (!robot_caps || inventory.clay_robots < blueprint.max_clay_use()),
+            obsidian: inventory.can_build_obsidian_robot(blueprint)
+                && (!robot_caps || inventory.obsidian_robots < blueprint.max_obsidian_use()),
+            geode: inventory.can_build_geode_robot(blueprint),
+        };
+        // pushed in reverse order of preference, so geode robots get explored first
+        work.push((geodes, inventory.next(), remaining_ticks - 1, affordable));
+        if affordable.clay && !declined.clay {
+            let mut next = inventory.next();
+            next.ore -= blueprint.clay_cost;
+            next.clay_robots += 1;
+            work.push((geodes, next, remaining_ticks - 1, Declined::default()))
+        }
+        if affordable.ore && !declined.ore {
+            let mut next = inventory.next();
+            next.ore -= blueprint.ore_cost;
+            next.ore_robots += 1;
+            work.push((geodes, next, remaining_ticks - 1, Declined::default()))
+        }
+        if affordable.obsidian && !declined.obsidian {
+            let mut next = inventory.next();
+            next.ore -= blueprint.obsidian_cost.0;
+            next.clay -= blueprint.obsidian_cost.1;
+            next.obsidian_robots += 1;
+            work.push((geodes, next, remaining_ticks - 1, Declined::default()))
+        }
+        if affordable.geode && !declined.geode {
+            let mut next = inventory.next();
+            next.ore -= blueprint.geode_cost.0;
+            next.obsidian -= blueprint.geode_cost.1;
+            work.push((
+                geodes + remaining_ticks - 1,
+                next,
+                remaining_ticks - 1,
+                Declined::default(),
+            ));
         }
-        // this trick is borred from vwoo; only consider the most successful fronts from this BFS
-        next_work.sort_by(|a, b| b.cmp(a));
-        next_work.truncate(std::cmp::min(next_work.len(), 10000));
-        std::mem::swap(&mut work, &mut next_work);
     }
     best
 }
 
+/// How long it took to find the best geode count for each blueprint
+type Results<'a> = [(&'a Blueprint, u16, Duration)];
+
+fn total_score(mode: Mode, results: &Results) -> u16 {
+    match mode {
+        Mode::Part1 => results.iter().map(|(b, g, _)| b.id * g).sum(),
+        Mode::Part2 => results.iter().map(|(_, g, _)| g).product(),
+    }
+}
+
+/// One line per blueprint, skipping any that crack fewer than `min_geodes` geodes
+fn report_lines(results: &Results, min_geodes: Option<u16>) -> Vec<String> {
+    results
+        .iter()
+        .filter(|(_, geodes, _)| *geodes >= min_geodes.unwrap_or(0))
+        .map(|(blueprint, geodes, elapsed)| {
+            format!(
+                "best score for {} {} (in {:?})",
+                blueprint.id, geodes, elapsed
+            )
+        })
+        .collect()
+}
+
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
     let log_level = if args.verbose {
@@ -210,31 +322,206 @@ fn main() -> anyhow::Result<()> {
         .format_timestamp_millis()
         .filter_level(log_level)
         .init();
-    let stdin = std::io::stdin();
-    let input = std::io::read_to_string(stdin)?;
+    let input = args.common.read_input()?;
+    let _timer = args.common.start_timer();
     let blueprints = parse_blueprints(&input)?;
     let minutes = match args.mode {
         Mode::Part1 => 24,
         Mode::Part2 => 32,
     };
-    let geodes = blueprints.par_iter().map(|blueprint| {
-        let start = std::time::Instant::now();
-        log::debug!("about to start simulating {:?}", blueprint);
-        let geodes = simulate_with(blueprint, Inventory::new(), minutes);
-        log::info!(
-            "best score for {} {} (in {:?})",
-            blueprint.id,
-            geodes,
-            start.elapsed()
-        );
-        (blueprint, geodes)
-    });
-    if args.mode == Mode::Part1 {
-        let total_score: u16 = geodes.map(|(b, g)| b.id * g).sum();
-        println!("{}", total_score);
-    } else {
-        let total_score: u16 = geodes.map(|(_, g)| g).take(3).product();
-        println!("{}", total_score);
+    let blueprints = match args.mode {
+        Mode::Part1 => &blueprints[..],
+        Mode::Part2 => &blueprints[..std::cmp::min(3, blueprints.len())],
+    };
+    let results = blueprints
+        .par_iter()
+        .map(|blueprint| {
+            let start = std::time::Instant::now();
+            log::debug!("about to start simulating {:?}", blueprint);
+            let inventory = Inventory::with_ore_robots(args.start_ore_robots);
+            let geodes = simulate_with(blueprint, inventory, minutes, !args.no_robot_caps);
+            (blueprint, geodes, start.elapsed())
+        })
+        .collect::<Vec<_>>();
+    for line in report_lines(&results, args.min_geodes) {
+        log::info!("{}", line);
     }
+    println!("{}", total_score(args.mode, &results));
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        optimistic_geodes, parse_blueprints, report_lines, simulate_with, total_score, Inventory,
+        Mode,
+    };
+    use std::time::Duration;
+
+    const SAMPLE: &str = "Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 2 ore. Each obsidian robot costs 3 ore and 14 clay. Each geode robot costs 2 ore and 7 obsidian.
+Blueprint 2: Each ore robot costs 2 ore. Each clay robot costs 3 ore. Each obsidian robot costs 3 ore and 8 clay. Each geode robot costs 3 ore and 12 obsidian.
+";
+
+    #[test]
+    fn test_deterministic() {
+        let blueprints = parse_blueprints(SAMPLE).unwrap();
+        let run = || {
+            blueprints
+                .iter()
+                .map(|b| format!("{} {}", b.id, simulate_with(b, Inventory::new(), 24, true)))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        let first = run();
+        assert_eq!(first, "1 9\n2 12");
+        for _ in 0..3 {
+            assert_eq!(run(), first);
+        }
+    }
+
+    #[test]
+    fn test_sample_24_and_32_minutes() {
+        let blueprints = parse_blueprints(SAMPLE).unwrap();
+        let scores = |minutes| {
+            blueprints
+                .iter()
+                .map(|b| simulate_with(b, Inventory::new(), minutes, true))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(scores(24), vec![9, 12]);
+        assert_eq!(scores(32), vec![56, 62]);
+    }
+
+    #[test]
+    fn test_optimistic_geodes_is_monotonic() {
+        let blueprints = parse_blueprints(SAMPLE).unwrap();
+        let mut inventory = Inventory::new();
+        inventory.obsidian = 5;
+        inventory.obsidian_robots = 1;
+        for blueprint in &blueprints {
+            for remaining in 0..32 {
+                assert!(
+                    optimistic_geodes(&inventory, blueprint, remaining)
+                        <= optimistic_geodes(&inventory, blueprint, remaining + 1)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_optimistic_geodes_never_underestimates() {
+        let blueprints = parse_blueprints(SAMPLE).unwrap();
+        // with plenty of everything, the best you can do in 3 minutes is a geode robot in
+        // each of the first two (2 + 1), since one built in the last minute never does anything
+        let mut rich = Inventory::new();
+        rich.ore = 100;
+        rich.obsidian = 100;
+        for blueprint in &blueprints {
+            assert_eq!(simulate_with(blueprint, rich.clone(), 3, true), 3);
+            assert_eq!(optimistic_geodes(&rich, blueprint, 3), 3);
+            for remaining in 0..=24 {
+                assert!(
+                    optimistic_geodes(&Inventory::new(), blueprint, remaining)
+                        >= simulate_with(blueprint, Inventory::new(), remaining, true)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_min_geodes_only_filters_log_lines() {
+        let blueprints = parse_blueprints(SAMPLE).unwrap();
+        let results = blueprints
+            .iter()
+            .map(|b| {
+                (
+                    b,
+                    simulate_with(b, Inventory::new(), 24, true),
+                    Duration::ZERO,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(report_lines(&results, None).len(), 2);
+        assert_eq!(report_lines(&results, Some(9)).len(), 2);
+        assert_eq!(
+            report_lines(&results, Some(10)),
+            vec!["best score for 2 12 (in 0ns)".to_string()]
+        );
+        assert!(report_lines(&results, Some(13)).is_empty());
+        assert_eq!(total_score(Mode::Part1, &results), 33);
+    }
+
+    #[test]
+    fn test_can_build_at_boundaries() {
+        let blueprints = parse_blueprints(SAMPLE).unwrap();
+        let blueprint = &blueprints[0];
+        let mut inventory = Inventory::new();
+        assert!(!inventory.can_build_ore_robot(blueprint));
+        assert!(!inventory.can_build_clay_robot(blueprint));
+
+        inventory.ore = 2;
+        assert!(inventory.can_build_clay_robot(blueprint));
+        assert!(!inventory.can_build_ore_robot(blueprint));
+        inventory.ore = 4;
+        assert!(inventory.can_build_ore_robot(blueprint));
+
+        // obsidian robots cost 3 ore and 14 clay
+        inventory.clay = 13;
+        assert!(!inventory.can_build_obsidian_robot(blueprint));
+        inventory.clay = 14;
+        assert!(inventory.can_build_obsidian_robot(blueprint));
+        inventory.ore = 2;
+        assert!(!inventory.can_build_obsidian_robot(blueprint));
+
+        // geode robots cost 2 ore and 7 obsidian
+        inventory.obsidian = 6;
+        assert!(!inventory.can_build_geode_robot(blueprint));
+        inventory.obsidian = 7;
+        assert!(inventory.can_build_geode_robot(blueprint));
+        inventory.ore = 1;
+        assert!(!inventory.can_build_geode_robot(blueprint));
+    }
+
+    #[test]
+    fn test_robot_caps_do_not_change_answer() {
+        let blueprints = parse_blueprints(SAMPLE).unwrap();
+        for blueprint in &blueprints {
+            assert_eq!(
+                simulate_with(blueprint, Inventory::new(), 24, false),
+                simulate_with(blueprint, Inventory::new(), 24, true)
+            );
+        }
+    }
+
+    #[test]
+    fn test_validation() {
+        let zero_cost = SAMPLE.replacen(
+            "Each clay robot costs 2 ore",
+            "Each clay robot costs 0 ore",
+            1,
+        );
+        let err = parse_blueprints(&zero_cost).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "blueprint 1: clay robot ore cost must be positive"
+        );
+
+        let duplicate = SAMPLE.replacen("Blueprint 2", "Blueprint 1", 1);
+        let err = parse_blueprints(&duplicate).unwrap_err();
+        assert_eq!(err.to_string(), "duplicate blueprint id 1");
+    }
+
+    #[test]
+    fn test_start_ore_robots() {
+        let blueprints = parse_blueprints(SAMPLE).unwrap();
+        for blueprint in blueprints.iter() {
+            let one = simulate_with(blueprint, Inventory::new(), 24, true);
+            let two = simulate_with(blueprint, Inventory::with_ore_robots(2), 24, true);
+            assert!(two >= one, "{} < {} for {:?}", two, one, blueprint);
+            assert_eq!(
+                simulate_with(blueprint, Inventory::with_ore_robots(0), 24, true),
+                0
+            );
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use clap::{Parser, ValueEnum};
+use derive_more::Display;
+
+#[derive(ValueEnum, Debug, PartialEq, Eq, Clone, Copy)]
+enum Mode {
+    /// The second column is what you play
+    Part1,
+    /// The second column is how the round should end
+    Part2,
+}
+
+#[derive(Debug, Parser)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[arg(short, long)]
+    verbose: bool,
+    #[arg(short, long, value_enum)]
+    mode: Mode,
+    #[command(flatten)]
+    common: aoclib::CommonArgs,
+}
+
+#[derive(Debug, Display, PartialEq, Eq, Clone, Copy)]
+enum Outcome {
+    Win,
+    Tie,
+    Loss,
+}
+
+impl Outcome {
+    fn score(&self) -> u32 {
+        match self {
+            Outcome::Win => 6,
+            Outcome::Tie => 3,
+            Outcome::Loss => 0,
+        }
+    }
+
+    fn from_you_should(c: char) -> anyhow::Result<Self> {
+        Ok(match c {
+            'X' => Outcome::Loss,
+            'Y' => Outcome::Tie,
+            'Z' => Outcome::Win,
+            other => anyhow::bail!("unexpected outcome {:?}", other),
+        })
+    }
+}
+
+#[derive(Debug, Display, PartialEq, Eq, Clone, Copy)]
+enum Rps {
+    Rock,
+    Paper,
+    Scissors,
+}
+
+impl Rps {
+    fn from_they_play(c: char) -> anyhow::Result<Self> {
+        Ok(match c {
+            'A' => Rps::Rock,
+            'B' => Rps::Paper,
+            'C' => Rps::Scissors,
+            other => anyhow::bail!("unexpected they-play input {:?}", other),
+        })
+    }
+
+    fn from_you_play(c: char) -> anyhow::Result<Self> {
+        Ok(match c {
+            'X' => Rps::Rock,
+            'Y' => Rps::Paper,
+            'Z' => Rps::Scissors,
+            other => anyhow::bail!("unexpected you-play input {:?}", other),
+        })
+    }
+
+    fn score(&self) -> u32 {
+        match self {
+            Rps::Rock => 1,
+            Rps::Paper => 2,
+            Rps::Scissors => 3,
+        }
+    }
+
+    fn beats(&self) -> Rps {
+        match self {
+            Rps::Rock => Rps::Scissors,
+            Rps::Paper => Rps::Rock,
+            Rps::Scissors => Rps::Paper,
+        }
+    }
+
+    fn is_beaten_by(&self) -> Self {
+        match self {
+            Rps::Rock => Rps::Paper,
+            Rps::Paper => Rps::Scissors,
+            Rps::Scissors => Rps::Rock,
+        }
+    }
+
+    fn play(&self, other: &Rps) -> Outcome {
+        if self == other {
+            Outcome::Tie
+        } else if self.beats() == *other {
+            Outcome::Win
+        } else {
+            Outcome::Loss
+        }
+    }
+
+    /// What to play against `self` to get the given outcome
+    fn for_outcome(&self, outcome: Outcome) -> Rps {
+        match outcome {
+            Outcome::Win => self.is_beaten_by(),
+            Outcome::Tie => *self,
+            Outcome::Loss => self.beats(),
+        }
+    }
+}
+
+fn score_round(you_play: Rps, they_play: Rps) -> u32 {
+    let outcome = you_play.play(&they_play);
+    let score = you_play.score() + outcome.score();
+    log::debug!(
+        "they play {}, you play {}, outcome: {}; score: {}",
+        they_play,
+        you_play,
+        outcome,
+        score
+    );
+    score
+}
+
+/// Score a single line of the strategy guide, interpreting the second column per `mode`;
+/// blank lines score nothing
+fn score_line(line: &str, mode: Mode) -> anyhow::Result<Option<u32>> {
+    let line = line.trim();
+    if line.is_empty() {
+        return Ok(None);
+    }
+    let chars = line.chars().collect::<Vec<_>>();
+    let [first, ' ', second] = chars[..] else {
+        anyhow::bail!("expected two columns, got {:?}", line);
+    };
+    let they_play = Rps::from_they_play(first)?;
+    let you_play = match mode {
+        Mode::Part1 => Rps::from_you_play(second)?,
+        Mode::Part2 => they_play.for_outcome(Outcome::from_you_should(second)?),
+    };
+    Ok(Some(score_round(you_play, they_play)))
+}
+
+fn total_score<I: Iterator<Item = String>>(lines: I, mode: Mode) -> anyhow::Result<u32> {
+    let mut total = 0;
+    for (line_number, line) in lines.enumerate() {
+        let score = score_line(&line, mode)
+            .map_err(|e| anyhow::anyhow!("line {}: {}", line_number + 1, e))?;
+        total += score.unwrap_or(0);
+    }
+    Ok(total)
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let log_level = if args.verbose {
+        log::LevelFilter::Debug
+    } else {
+        log::LevelFilter::Info
+    };
+    env_logger::builder()
+        .format_module_path(false)
+        .format_timestamp_millis()
+        .filter_level(log_level)
+        .init();
+    let input = args.common.read_input()?;
+    let _timer = args.common.start_timer();
+    let total = total_score(input.lines().map(str::to_owned), args.mode)?;
+    println!("{}", total);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{total_score, Mode, Outcome, Rps};
+
+    const SAMPLE: &str = "A Y
+B X
+C Z
+";
+
+    #[test]
+    fn test_sample() {
+        let lines = || SAMPLE.lines().map(String::from);
+        assert_eq!(total_score(lines(), Mode::Part1).unwrap(), 15);
+        assert_eq!(total_score(lines(), Mode::Part2).unwrap(), 12);
+    }
+
+    #[test]
+    fn test_for_outcome() {
+        for they_play in [Rps::Rock, Rps::Paper, Rps::Scissors] {
+            for outcome in [Outcome::Win, Outcome::Tie, Outcome::Loss] {
+                assert_eq!(they_play.for_outcome(outcome).play(&they_play), outcome);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bad_input() {
+        let lines = || "A Y\nA Q\n".lines().map(String::from);
+        assert_eq!(
+            total_score(lines(), Mode::Part1).unwrap_err().to_string(),
+            "line 2: unexpected you-play input 'Q'"
+        );
+        assert_eq!(
+            total_score(lines(), Mode::Part2).unwrap_err().to_string(),
+            "line 2: unexpected outcome 'Q'"
+        );
+        let lines = || "D X\nAX\n".lines().map(String::from);
+        assert_eq!(
+            total_score(lines(), Mode::Part1).unwrap_err().to_string(),
+            "line 1: unexpected they-play input 'D'"
+        );
+        let lines = || "\nAX\n".lines().map(String::from);
+        assert_eq!(
+            total_score(lines(), Mode::Part1).unwrap_err().to_string(),
+            "line 2: expected two columns, got \"AX\""
+        );
+    }
+}
@@ -1,10 +1,5 @@
-use clap::{Parser, ValueEnum};
-
-#[derive(ValueEnum, Debug, PartialEq, Eq, Clone, Copy)]
-enum Mode {
-    Part1,
-    Part2,
-}
+use aoclib::days::day20::{solve, solve_with, Mode};
+use clap::Parser;
 
 #[derive(Debug, Parser)]
 #[command(author, version, about, long_about = None)]
@@ -13,41 +8,18 @@ struct Args {
     verbose: bool,
     #[clap(short, long, value_enum)]
     mode: Mode,
-}
-
-#[derive(Debug, Copy, Clone)]
-struct Item {
-    value: i64,
-    original_index: usize,
-}
-
-fn mix(input: &Vec<i64>, decryption_key: i64, rounds: usize) -> Vec<i64> {
-    let mut output = input
-        .iter()
-        .enumerate()
-        .map(|(i, v)| Item {
-            value: *v * decryption_key,
-            original_index: i,
-        })
-        .collect::<Vec<Item>>();
-    let len = input.len();
-    for _ in 0..rounds {
-        log::debug!("{:?}", input);
-        for index in 0..input.len() {
-            let position = output
-                .iter()
-                .position(|p| p.original_index == index)
-                .unwrap();
-            let v = output[position].value;
-            log::debug!("examining original index {}, value {}", index, v);
-            let new_position = (position as i64 + v).rem_euclid(len as i64 - 1) as usize;
-            let value = output.remove(position);
-            output.insert(new_position, value);
-            log::debug!("{} -> {} (by {})", position, new_position, v);
-            log::debug!("{:?}\n", output.iter().map(|m| m.value).collect::<Vec<_>>());
-        }
-    }
-    output.into_iter().map(|m| m.value).collect()
+    /// Multiply every value by this before mixing (defaults to 1 in part 1 and the
+    /// decryption key in part 2)
+    #[clap(short, long, value_parser)]
+    key: Option<i64>,
+    /// Number of mixing passes (defaults to 1 in part 1 and 10 in part 2)
+    #[clap(short, long, value_parser)]
+    rounds: Option<usize>,
+    /// Print the whole ring after each mixing round
+    #[clap(long)]
+    dump_rounds: bool,
+    #[command(flatten)]
+    common: aoclib::CommonArgs,
 }
 
 fn main() -> anyhow::Result<()> {
@@ -62,24 +34,22 @@ fn main() -> anyhow::Result<()> {
         .format_timestamp_millis()
         .filter_level(log_level)
         .init();
-    let stdin = std::io::stdin();
-    let input = std::io::read_to_string(stdin)?;
-    let numbers = input
-        .lines()
-        .map(|l| l.parse::<i64>())
-        .collect::<Result<Vec<_>, _>>()?;
-    let result = match args.mode {
-        Mode::Part1 => mix(&numbers, 1, 1),
-        Mode::Part2 => mix(&numbers, 811589153, 10),
+    let input = args.common.read_input()?;
+    let _timer = args.common.start_timer();
+    let result = if args.dump_rounds {
+        solve_with(&input, args.mode, args.key, args.rounds, |round, ring| {
+            println!(
+                "after round {}: {}",
+                round,
+                ring.iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        })?
+    } else {
+        solve(&input, args.mode, args.key, args.rounds)?
     };
-    let zero_index = result.iter().position(|i| *i == 0).unwrap();
-    let sum: i64 = [1000, 2000, 3000]
-        .into_iter()
-        .map(|i| {
-            let index = (zero_index + i) % result.len();
-            result[index]
-        })
-        .sum();
-    println!("{}", sum);
+    println!("{}", result);
     Ok(())
 }
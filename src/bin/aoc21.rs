@@ -11,7 +11,7 @@ use nom::{
 };
 use petgraph::graph::{DiGraph, NodeIndex};
 
-type Value = f64;
+type Value = i128;
 
 #[derive(ValueEnum, Debug, PartialEq, Eq, Clone, Copy)]
 enum Mode {
@@ -24,8 +24,13 @@ enum Mode {
 struct Args {
     #[clap(short, long)]
     verbose: bool,
+    /// Don't log anything; only print the answer
+    #[clap(short, long, conflicts_with = "verbose")]
+    quiet: bool,
     #[clap(short, long, value_enum)]
     mode: Mode,
+    #[command(flatten)]
+    common: aoclib::CommonArgs,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -37,13 +42,22 @@ enum Operation {
 }
 
 impl Operation {
-    fn execute(&self, lhs: Value, rhs: Value) -> Value {
-        match self {
-            Self::Add => lhs + rhs,
-            Self::Sub => lhs - rhs,
-            Self::Mul => lhs * rhs,
-            Self::Div => lhs / rhs,
-        }
+    /// Apply the operation, failing on overflow or if a division would not be exact
+    fn execute(&self, lhs: Value, rhs: Value) -> anyhow::Result<Value> {
+        let res = match self {
+            Self::Add => lhs.checked_add(rhs),
+            Self::Sub => lhs.checked_sub(rhs),
+            Self::Mul => lhs.checked_mul(rhs),
+            Self::Div => {
+                if rhs == 0 {
+                    anyhow::bail!("division by zero: {} / {}", lhs, rhs);
+                } else if lhs % rhs != 0 {
+                    anyhow::bail!("inexact division: {} / {}", lhs, rhs);
+                }
+                Some(lhs / rhs)
+            }
+        };
+        res.ok_or_else(|| anyhow::anyhow!("overflow computing {} {:?} {}", lhs, self, rhs))
     }
 
     fn inverse(&self) -> Self {
@@ -87,15 +101,15 @@ impl Job {
         panic!("invalid argument passed to other_side_of");
     }
 
-    fn execute(&self, values: &BTreeMap<String, Value>) -> Value {
+    fn execute(&self, values: &BTreeMap<String, Value>) -> anyhow::Result<Value> {
         match self {
-            Job::Literal(i) => *i,
+            Job::Literal(i) => Ok(*i),
             Job::Op { lhs, op, rhs } => {
                 let lhs_value = values.get(lhs).unwrap();
                 let rhs_value = values.get(rhs).unwrap();
                 op.execute(*lhs_value, *rhs_value)
             }
-            Job::Variable => panic!("cannot evaluate a variable"),
+            Job::Variable => anyhow::bail!("cannot evaluate a variable"),
         }
     }
 }
@@ -124,7 +138,9 @@ fn parse_job(s: &str) -> IResult<&str, (String, Job)> {
         preceded(
             tag(": "),
             alt((
-                map(nom::character::complete::i64, |i| Job::Literal(i as Value)),
+                map(nom::character::complete::i64, |i| {
+                    Job::Literal(Value::from(i))
+                }),
                 map(
                     nom::sequence::tuple((
                         terminated(parse_operand, tag(" ")),
@@ -177,29 +193,29 @@ impl Evaluator {
         }
     }
 
-    fn partially_evaluate(&mut self, target_node: NodeIndex) -> Value {
+    fn partially_evaluate(&mut self, target_node: NodeIndex) -> anyhow::Result<Value> {
         let mut visitor = petgraph::visit::DfsPostOrder::new(&self.graph, target_node);
         let label = self.graph.node_weight(target_node).unwrap().clone();
         while let Some(item) = visitor.next(&self.graph) {
             let label = self.graph.node_weight(item).unwrap();
             let job = self.jobs.get(label).unwrap();
-            let value = job.execute(&self.values);
+            let value = job.execute(&self.values)?;
             self.values.insert(label.clone(), value);
         }
-        *self.values.get(&label).unwrap()
+        Ok(*self.values.get(&label).unwrap())
     }
 
-    fn partially_evaluate_operand(&mut self, operand: &str) -> Value {
+    fn partially_evaluate_operand(&mut self, operand: &str) -> anyhow::Result<Value> {
         let node = self.nodes.get(operand).unwrap();
         self.partially_evaluate(*node)
     }
 
-    fn evaluate_part1(&mut self, target: &str) -> Value {
+    fn evaluate_part1(&mut self, target: &str) -> anyhow::Result<Value> {
         let target_node = self.nodes.get(target).unwrap();
         self.partially_evaluate(*target_node)
     }
 
-    fn evaluate_part2(&mut self) -> Value {
+    fn evaluate_part2(&mut self) -> anyhow::Result<Value> {
         self.jobs.insert("humn".to_owned(), Job::Variable);
         let us = self.nodes.get("humn").unwrap();
         let root = self.nodes.get("root").unwrap();
@@ -208,7 +224,7 @@ impl Evaluator {
             petgraph::algo::astar(&self.graph, *root, |f| f == *us, |_| 1, |_| 0).unwrap();
         let this_side = self.graph.node_weight(path_to_root[1]).unwrap();
         let other_side = self.jobs["root"].other_side_of(this_side);
-        let mut value = self.partially_evaluate_operand(&other_side);
+        let mut value = self.partially_evaluate_operand(&other_side)?;
         // now, walk down the graph from root, inverting each operation as we go
         log::debug!("other side of root ({:?}) is {}", other_side, value);
         let nodes_in_this_path = path_to_root
@@ -221,30 +237,34 @@ impl Evaluator {
             log::debug!("walking through {} ({:?})", node_label, job);
             if let Job::Op { lhs, op, rhs } = job {
                 if nodes_in_this_path.contains(&lhs) {
-                    let rhs_value = self.partially_evaluate_operand(&rhs);
-                    value = op.inverse().execute(value, rhs_value);
+                    let rhs_value = self.partially_evaluate_operand(&rhs)?;
+                    value = op.inverse().execute(value, rhs_value)?;
                 } else if nodes_in_this_path.contains(&rhs) {
-                    let lhs_value = self.partially_evaluate_operand(&lhs);
+                    let lhs_value = self.partially_evaluate_operand(&lhs)?;
                     // this bit is tricky because of stupid commutativity rules
                     value = match op {
-                        Operation::Mul | Operation::Add => op.inverse().execute(value, lhs_value),
-                        Operation::Sub | Operation::Div => op.execute(lhs_value, value),
+                        Operation::Mul | Operation::Add => {
+                            op.inverse().execute(value, lhs_value)?
+                        }
+                        Operation::Sub | Operation::Div => op.execute(lhs_value, value)?,
                     };
                 } else {
                     panic!("wut");
                 }
             } else if let Job::Variable = job {
-                return value;
+                return Ok(value);
             }
             log::debug!("value = {}", value);
         }
-        panic!("failed to find solution");
+        anyhow::bail!("failed to find solution");
     }
 }
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
-    let log_level = if args.verbose {
+    let log_level = if args.quiet {
+        log::LevelFilter::Off
+    } else if args.verbose {
         log::LevelFilter::Debug
     } else {
         log::LevelFilter::Info
@@ -254,16 +274,51 @@ fn main() -> anyhow::Result<()> {
         .format_timestamp_millis()
         .filter_level(log_level)
         .init();
-    let stdin = std::io::stdin();
-    let input = std::io::read_to_string(stdin)?;
-    let start = std::time::Instant::now();
+    let input = args.common.read_input()?;
+    let _timer = args.common.start_timer();
     let jobs = parse_jobs(&input)?;
     let mut e = Evaluator::new(jobs);
     let res = match args.mode {
-        Mode::Part1 => e.evaluate_part1("root"),
-        Mode::Part2 => e.evaluate_part2(),
+        Mode::Part1 => e.evaluate_part1("root")?,
+        Mode::Part2 => e.evaluate_part2()?,
     };
-    log::info!("computed result in {:?}", start.elapsed());
     println!("{}", res);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_jobs, Evaluator, Operation};
+
+    const SAMPLE: &str = "root: pppw + sjmn
+dbpl: 5
+cczh: sllz + lgvd
+zczc: 2
+ptdq: humn - dvpt
+dvpt: 3
+lfqf: 4
+humn: 5
+ljgn: 2
+sjmn: drzm * dbpl
+sllz: 4
+pppw: cczh / lfqf
+lgvd: ljgn * ptdq
+drzm: hmdt - zczc
+hmdt: 32
+";
+
+    #[test]
+    fn test_sample() {
+        let mut e = Evaluator::new(parse_jobs(SAMPLE).unwrap());
+        assert_eq!(e.evaluate_part1("root").unwrap(), 152);
+        let mut e = Evaluator::new(parse_jobs(SAMPLE).unwrap());
+        assert_eq!(e.evaluate_part2().unwrap(), 301);
+    }
+
+    #[test]
+    fn test_inexact_division() {
+        assert_eq!(Operation::Div.execute(12, 4).unwrap(), 3);
+        assert!(Operation::Div.execute(13, 4).is_err());
+        assert!(Operation::Div.execute(13, 0).is_err());
+    }
+}
@@ -23,6 +23,11 @@ struct Args {
     verbose: bool,
     #[clap(short, long, value_enum)]
     mode: Mode,
+    /// Once done, draw just the path that was walked on an otherwise blank grid
+    #[clap(long)]
+    trace_only: bool,
+    #[command(flatten)]
+    common: aoclib::CommonArgs,
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
@@ -42,6 +47,14 @@ impl Cell {
             Cell::Wall => '#',
         }
     }
+
+    /// Like `as_char`, but only draws the path
+    fn trace_char(&self) -> char {
+        match self {
+            Cell::Traversed(d) => d.as_char(),
+            _ => ' ',
+        }
+    }
 }
 
 impl HasEmpty for Cell {
@@ -81,6 +94,15 @@ impl Direction {
         }
     }
 
+    fn reverse(&self) -> Self {
+        match self {
+            Self::Up => Self::Down,
+            Self::Down => Self::Up,
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+        }
+    }
+
     fn score(&self) -> u32 {
         match self {
             Self::Up => 3,
@@ -186,41 +208,177 @@ fn step_part1(
     }
 }
 
-fn get_face(position: Point, face_size: usize) -> u8 {
-    if face_size == 4 {
-        if position.y < 5 {
-            1
-        } else if position.y < 9 && position.x < 5 {
-            2
-        } else if position.y < 9 && position.x < 9 {
-            3
-        } else if position.y < 9 && position.x < 13 {
-            4
-        } else if position.y > 8 && position.y < 13 && position.x > 8 && position.x < 13 {
-            5
-        } else if position.y > 8 && position.y < 13 && position.x > 12 && position.x < 17 {
-            6
-        } else {
-            unreachable!()
+type Vec3 = [i64; 3];
+
+fn neg(v: Vec3) -> Vec3 {
+    [-v[0], -v[1], -v[2]]
+}
+
+/// One face of the cube, as laid out in the net
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Face {
+    /// Grid coordinate of the upper-left cell of this face
+    origin: Point,
+    /// Outward normal of this face once folded
+    normal: Vec3,
+    /// Direction in 3-space of increasing x on this face
+    right: Vec3,
+    /// Direction in 3-space of increasing y on this face
+    down: Vec3,
+}
+
+impl Face {
+    /// The outward direction (in 3-space) of the edge you cross leaving in `direction`
+    fn edge_normal(&self, direction: Direction) -> Vec3 {
+        match direction {
+            Direction::Right => self.right,
+            Direction::Left => neg(self.right),
+            Direction::Down => self.down,
+            Direction::Up => neg(self.down),
         }
-    } else if face_size == 50 {
-        if position.x < 101 && position.y < 51 {
-            1
-        } else if position.y < 51 {
-            2
-        } else if position.y > 50 && position.y < 101 {
-            3
-        } else if position.y < 151 && position.x < 51 {
-            4
-        } else if position.y < 151 && position.x < 101 {
-            5
-        } else if position.y < 201 && position.x < 51 {
-            6
-        } else {
-            unreachable!();
+    }
+
+    /// The orientation of the face adjacent to this one in the net in `direction`, folded
+    /// along their shared edge
+    fn fold(&self, direction: Direction) -> (Vec3, Vec3, Vec3) {
+        match direction {
+            Direction::Right => (self.right, neg(self.normal), self.down),
+            Direction::Left => (neg(self.right), self.normal, self.down),
+            Direction::Down => (self.down, self.right, neg(self.normal)),
+            Direction::Up => (neg(self.down), self.right, self.normal),
         }
-    } else {
-        panic!("unhandled face size {:?}", 50)
+    }
+}
+
+/// A cube folded from the net in the grid, used to compute where you end up when you walk
+/// off the edge of a face
+#[derive(Debug)]
+struct Cube {
+    face_size: i64,
+    faces: Vec<Face>,
+}
+
+impl Cube {
+    fn fold(grid: &DenseGrid<Cell>) -> anyhow::Result<Self> {
+        let area = (1..=grid.height() as i64)
+            .flat_map(|y| (1..=grid.width() as i64).map(move |x| Point::new(x, y)))
+            .filter(|p| grid.get(*p) != Some(Cell::Missing))
+            .count();
+        let face_size = ((area / 6) as f64).sqrt() as i64;
+        if area == 0 || face_size * face_size * 6 != area as i64 {
+            anyhow::bail!("{} cells cannot be folded into a cube", area);
+        }
+        let occupied = |fx: i64, fy: i64| {
+            let origin = Point::new(fx * face_size + 1, fy * face_size + 1);
+            !matches!(grid.get(origin), None | Some(Cell::Missing))
+        };
+        let start = (0..grid.width() as i64 / face_size)
+            .find(|fx| occupied(*fx, 0))
+            .ok_or_else(|| anyhow::anyhow!("no face in the first row"))?;
+        let mut faces = vec![Face {
+            origin: Point::new(start * face_size + 1, 1),
+            normal: [0, 0, 1],
+            right: [1, 0, 0],
+            down: [0, 1, 0],
+        }];
+        let mut queue = std::collections::VecDeque::from([0]);
+        while let Some(index) = queue.pop_front() {
+            let face = faces[index];
+            let fx = (face.origin.x - 1) / face_size;
+            let fy = (face.origin.y - 1) / face_size;
+            for direction in [
+                Direction::Right,
+                Direction::Down,
+                Direction::Left,
+                Direction::Up,
+            ] {
+                let (nfx, nfy) = match direction {
+                    Direction::Right => (fx + 1, fy),
+                    Direction::Left => (fx - 1, fy),
+                    Direction::Down => (fx, fy + 1),
+                    Direction::Up => (fx, fy - 1),
+                };
+                let origin = Point::new(nfx * face_size + 1, nfy * face_size + 1);
+                if nfx < 0 || nfy < 0 || !occupied(nfx, nfy) {
+                    continue;
+                }
+                if faces.iter().any(|f| f.origin == origin) {
+                    continue;
+                }
+                let (normal, right, down) = face.fold(direction);
+                faces.push(Face {
+                    origin,
+                    normal,
+                    right,
+                    down,
+                });
+                queue.push_back(faces.len() - 1);
+            }
+        }
+        let distinct_normals = faces
+            .iter()
+            .map(|f| f.normal)
+            .collect::<std::collections::BTreeSet<_>>();
+        if faces.len() != 6 || distinct_normals.len() != 6 {
+            anyhow::bail!("grid is not a valid cube net");
+        }
+        Ok(Self { face_size, faces })
+    }
+
+    fn face_containing(&self, position: Point) -> &Face {
+        self.faces
+            .iter()
+            .find(|f| {
+                (f.origin.x..f.origin.x + self.face_size).contains(&position.x)
+                    && (f.origin.y..f.origin.y + self.face_size).contains(&position.y)
+            })
+            .unwrap()
+    }
+
+    /// Find where you end up walking off the edge of a face from `position`
+    fn wrap(&self, position: Point, direction: Direction) -> (Point, Direction) {
+        let face = self.face_containing(position);
+        let n = self.face_size;
+        let local_x = position.x - face.origin.x;
+        let local_y = position.y - face.origin.y;
+        let edge_normal = face.edge_normal(direction);
+        // distance along the edge we are crossing, and the direction that's measured in
+        let (along, along_vector) = match direction {
+            Direction::Left | Direction::Right => (local_y, face.down),
+            Direction::Up | Direction::Down => (local_x, face.right),
+        };
+        let target = self.faces.iter().find(|f| f.normal == edge_normal).unwrap();
+        // we come in through whichever edge of the target points toward the face we're
+        // leaving, so we head away from that edge
+        let new_direction = [
+            Direction::Right,
+            Direction::Down,
+            Direction::Left,
+            Direction::Up,
+        ]
+        .into_iter()
+        .find(|d| target.edge_normal(*d) == face.normal)
+        .unwrap()
+        .reverse();
+        let target_along_vector = match new_direction {
+            Direction::Left | Direction::Right => target.down,
+            Direction::Up | Direction::Down => target.right,
+        };
+        let along = if target_along_vector == along_vector {
+            along
+        } else {
+            n - 1 - along
+        };
+        let (new_x, new_y) = match new_direction {
+            Direction::Right => (0, along),
+            Direction::Left => (n - 1, along),
+            Direction::Down => (along, 0),
+            Direction::Up => (along, n - 1),
+        };
+        (
+            Point::new(target.origin.x + new_x, target.origin.y + new_y),
+            new_direction,
+        )
     }
 }
 
@@ -228,7 +386,7 @@ fn step_part2(
     position: Point,
     direction: Direction,
     grid: &DenseGrid<Cell>,
-    face_size: usize,
+    cube: &Cube,
 ) -> (Point, Direction, Cell) {
     let increment = match direction {
         Direction::Right => Point::new(1, 0),
@@ -242,197 +400,21 @@ fn step_part2(
         Some(other) => return (next_position, direction, other),
         _ => {}
     };
-    let face = get_face(position, face_size);
-    log::debug!("wraparound at {} ({})", position, face);
-    let adjacencies = if face_size == 4 {
-        vec![
-            (
-                Direction::Left,
-                1,
-                Point::new(position.y + 4, 5),
-                Direction::Down,
-            ),
-            (
-                Direction::Up,
-                1,
-                Point::new(13 - position.x, 5),
-                Direction::Down,
-            ),
-            (
-                Direction::Right,
-                1,
-                Point::new(16, 13 - position.y),
-                Direction::Left,
-            ),
-            (
-                Direction::Left,
-                2,
-                Point::new(21 - position.y, 12),
-                Direction::Up,
-            ),
-            (
-                Direction::Up,
-                2,
-                Point::new(13 - position.x, 1),
-                Direction::Down,
-            ),
-            (
-                Direction::Down,
-                2,
-                Point::new(13 - position.x, 12),
-                Direction::Down,
-            ),
-            (
-                Direction::Up,
-                3,
-                Point::new(9, position.x - 4),
-                Direction::Right,
-            ),
-            (
-                Direction::Down,
-                3,
-                Point::new(9, 17 - position.x),
-                Direction::Right,
-            ),
-            (
-                Direction::Right,
-                4,
-                Point::new(21 - position.y, 9),
-                Direction::Down,
-            ),
-            (
-                Direction::Left,
-                5,
-                Point::new(17 - position.y, 8),
-                Direction::Up,
-            ),
-            (
-                Direction::Down,
-                5,
-                Point::new(13 - position.x, 8),
-                Direction::Up,
-            ),
-            (
-                Direction::Down,
-                6,
-                Point::new(1, 21 - position.x),
-                Direction::Right,
-            ),
-            (
-                Direction::Right,
-                6,
-                Point::new(12, 13 - position.y),
-                Direction::Left,
-            ),
-            (
-                Direction::Up,
-                6,
-                Point::new(12, 21 - position.y),
-                Direction::Left,
-            ),
-        ]
-    } else if face_size == 50 {
-        vec![
-            (
-                Direction::Left,
-                1,
-                Point::new(1, 151 - position.y),
-                Direction::Right,
-            ),
-            (
-                Direction::Up,
-                1,
-                Point::new(1, position.x + 100),
-                Direction::Right,
-            ),
-            (
-                Direction::Up,
-                2,
-                Point::new(position.x - 100, 200),
-                Direction::Up,
-            ),
-            (
-                Direction::Right,
-                2,
-                Point::new(100, 151 - position.y),
-                Direction::Left,
-            ),
-            (
-                Direction::Down,
-                2,
-                Point::new(100, position.x - 50),
-                Direction::Left,
-            ),
-            (
-                Direction::Right,
-                3,
-                Point::new(position.y + 50, 50),
-                Direction::Up,
-            ),
-            (
-                Direction::Right,
-                5,
-                Point::new(150, 151 - position.y),
-                Direction::Left,
-            ),
-            (
-                Direction::Down,
-                5,
-                Point::new(50, position.x + 100),
-                Direction::Left,
-            ),
-            (
-                Direction::Right,
-                6,
-                Point::new(position.y - 100, 150),
-                Direction::Up,
-            ),
-            (
-                Direction::Down,
-                6,
-                Point::new(position.x + 100, 1),
-                Direction::Down,
-            ),
-            (
-                Direction::Left,
-                6,
-                Point::new(position.y - 100, 1),
-                Direction::Down,
-            ),
-            (
-                Direction::Left,
-                4,
-                Point::new(51, 151 - position.y),
-                Direction::Right,
-            ),
-            (
-                Direction::Up,
-                4,
-                Point::new(51, position.x + 50),
-                Direction::Right,
-            ),
-            (
-                Direction::Left,
-                3,
-                Point::new(position.y - 50, 101),
-                Direction::Down,
-            ),
-        ]
-    } else {
-        panic!("unhandled face size {}", face_size);
-    };
-    for (pdirection, pface, point, new_direction) in adjacencies {
-        if pdirection == direction && pface == face {
-            return (point, new_direction, grid.get(point).unwrap());
-        }
-    }
-    panic!(
-        "no transition found for face {} going {:?}",
-        face, direction
+    let (next_position, next_direction) = cube.wrap(position, direction);
+    log::debug!(
+        "wraparound at {} -> {} going {:?}",
+        position,
+        next_position,
+        next_direction
     );
+    (
+        next_position,
+        next_direction,
+        grid.get(next_position).unwrap(),
+    )
 }
 
-fn simulate(board: &mut Board, mode: Mode) -> u32 {
+fn simulate(board: &mut Board, mode: Mode) -> anyhow::Result<u32> {
     let first_empty = (1..=board.grid.width())
         .find_map(|x| {
             let coordinate = Point::new(x as i64, 1);
@@ -443,15 +425,14 @@ fn simulate(board: &mut Board, mode: Mode) -> u32 {
             }
         })
         .unwrap();
-    let face_size = (1..board.grid.height())
-        .map(|y| {
-            (1..board.grid.width())
-                .filter(|x| board.grid.get(Point::new(*x as i64, y as i64)) != Some(Cell::Missing))
-                .count()
-        })
-        .min()
-        .unwrap();
-    log::info!("part 2 face size is {}", face_size);
+    let cube = match mode {
+        Mode::Part1 => None,
+        Mode::Part2 => {
+            let cube = Cube::fold(&board.grid)?;
+            log::info!("part 2 face size is {}", cube.face_size);
+            Some(cube)
+        }
+    };
     let mut state = State {
         position: first_empty,
         direction: Direction::Right,
@@ -468,10 +449,10 @@ fn simulate(board: &mut Board, mode: Mode) -> u32 {
                     board
                         .grid
                         .set(state.position, Cell::Traversed(state.direction));
-                    let (next, direction, cell) = match mode {
-                        Mode::Part1 => step_part1(state.position, state.direction, &board.grid),
-                        Mode::Part2 => {
-                            step_part2(state.position, state.direction, &board.grid, face_size)
+                    let (next, direction, cell) = match cube {
+                        None => step_part1(state.position, state.direction, &board.grid),
+                        Some(ref cube) => {
+                            step_part2(state.position, state.direction, &board.grid, cube)
                         }
                     };
                     match cell {
@@ -495,7 +476,7 @@ fn simulate(board: &mut Board, mode: Mode) -> u32 {
     board
         .grid
         .set(state.position, Cell::Traversed(state.direction));
-    1000 * state.position.y as u32 + 4 * state.position.x as u32 + state.direction.score()
+    Ok(1000 * state.position.y as u32 + 4 * state.position.x as u32 + state.direction.score())
 }
 
 fn parse_board(s: &str) -> anyhow::Result<Board> {
@@ -525,16 +506,142 @@ fn main() -> anyhow::Result<()> {
         .format_timestamp_millis()
         .filter_level(log_level)
         .init();
-    let stdin = std::io::stdin();
-    let input = std::io::read_to_string(stdin)?;
+    let input = args.common.read_input()?;
+    let _timer = args.common.start_timer();
     let mut board = parse_board(&input)?;
     if args.verbose {
         board.grid.dump_with(Cell::as_char)
     }
-    let score = simulate(&mut board, args.mode);
+    let score = simulate(&mut board, args.mode)?;
     if args.verbose {
         board.grid.dump_with(Cell::as_char)
     }
+    if args.trace_only {
+        board.grid.dump_with(Cell::trace_char)
+    }
     println!("{}", score);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        parse_board, parse_grid, simulate, step_part2, Cell, Cube, Direction, Mode, Point,
+    };
+
+    const SAMPLE: &str = "        ...#
+        .#..
+        #...
+        ....
+...#.......#
+........#...
+..#....#....
+..........#.
+        ...#....
+        .....#..
+        .#......
+        ......#.
+
+10R5L5R10L4R5L5
+";
+
+    /// Build a wall-free net with faces at the given (face x, face y) offsets
+    fn blank_net(layout: &[(usize, usize)], face_size: usize) -> String {
+        let width = layout.iter().map(|(x, _)| x + 1).max().unwrap() * face_size;
+        let height = layout.iter().map(|(_, y)| y + 1).max().unwrap() * face_size;
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| {
+                        if layout.contains(&(x / face_size, y / face_size)) {
+                            '.'
+                        } else {
+                            ' '
+                        }
+                    })
+                    .collect::<String>()
+                    .trim_end()
+                    .to_owned()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// On a cube with no walls, walking 4 faces in a straight line gets you back to where
+    /// you started
+    fn assert_walks_loop(layout: &[(usize, usize)], face_size: usize) {
+        let (_, grid) = parse_grid(&blank_net(layout, face_size)).unwrap();
+        let cube = Cube::fold(&grid).unwrap();
+        let n = face_size as i64;
+        for (fx, fy) in layout {
+            for offset in 0..n {
+                for direction in [
+                    Direction::Right,
+                    Direction::Down,
+                    Direction::Left,
+                    Direction::Up,
+                ] {
+                    let start = Point::new(*fx as i64 * n + 1 + offset, *fy as i64 * n + 1);
+                    let mut position = start;
+                    let mut heading = direction;
+                    for _ in 0..4 * n {
+                        let (next, next_heading, _) = step_part2(position, heading, &grid, &cube);
+                        position = next;
+                        heading = next_heading;
+                    }
+                    assert_eq!((position, heading), (start, direction));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_sample() {
+        let mut board = parse_board(SAMPLE).unwrap();
+        assert_eq!(simulate(&mut board, Mode::Part1).unwrap(), 6032);
+        let mut board = parse_board(SAMPLE).unwrap();
+        assert_eq!(simulate(&mut board, Mode::Part2).unwrap(), 5031);
+    }
+
+    #[test]
+    fn test_nets_fold() {
+        // the sample layout
+        assert_walks_loop(&[(2, 0), (0, 1), (1, 1), (2, 1), (2, 2), (3, 2)], 4);
+        // the layout of the real inputs
+        assert_walks_loop(&[(1, 0), (2, 0), (1, 1), (0, 2), (1, 2), (0, 3)], 50);
+        // a cross
+        assert_walks_loop(&[(1, 0), (0, 1), (1, 1), (2, 1), (1, 2), (1, 3)], 3);
+        // a staircase
+        assert_walks_loop(&[(0, 0), (0, 1), (1, 1), (1, 2), (2, 2), (2, 3)], 2);
+    }
+
+    #[test]
+    fn test_invalid_net() {
+        let (_, grid) = parse_grid(&blank_net(
+            &[(0, 0), (1, 0), (2, 0), (3, 0), (4, 0), (5, 0)],
+            2,
+        ))
+        .unwrap();
+        assert!(Cube::fold(&grid).is_err());
+    }
+
+    #[test]
+    fn test_trace_only_draws_one_arrow_per_traversed_cell() {
+        for mode in [Mode::Part1, Mode::Part2] {
+            let mut board = parse_board(SAMPLE).unwrap();
+            simulate(&mut board, mode).unwrap();
+            let mut out = Vec::new();
+            board.grid.dump_to(&mut out, Cell::trace_char).unwrap();
+            let out = String::from_utf8(out).unwrap();
+            assert!(!out.contains(['#', '.']));
+            let arrows = out.chars().filter(|c| "<>^v".contains(*c)).count();
+            let (width, height) = (board.grid.width() as i64, board.grid.height() as i64);
+            let traversed = (1..=width)
+                .flat_map(|x| (1..=height).map(move |y| Point::new(x, y)))
+                .filter(|p| matches!(board.grid.get(*p), Some(Cell::Traversed(_))))
+                .count();
+            assert!(arrows > 0);
+            assert_eq!(arrows, traversed);
+        }
+    }
+}
@@ -1,8 +1,8 @@
 use clap::{Parser, ValueEnum};
-use itertools::{Itertools, MinMaxResult};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use aoclib::DenseGrid;
+use aoclib::Direction;
 use aoclib::Point;
 
 #[derive(ValueEnum, Debug, PartialEq, Eq, Clone, Copy)]
@@ -11,6 +11,42 @@ enum Mode {
     Part2,
 }
 
+/// Which neighbors must be empty for an elf to sit a round out
+#[derive(ValueEnum, Debug, PartialEq, Eq, Clone, Copy)]
+enum Neighborhood {
+    #[value(name = "4")]
+    Four,
+    #[value(name = "8")]
+    Eight,
+}
+
+const FOUR_NEIGHBORS: [Point; 4] = [
+    Point::new(-1, 0),
+    Point::new(0, -1),
+    Point::new(0, 1),
+    Point::new(1, 0),
+];
+
+const EIGHT_NEIGHBORS: [Point; 8] = [
+    Point::new(-1, -1),
+    Point::new(-1, 0),
+    Point::new(-1, 1),
+    Point::new(0, -1),
+    Point::new(0, 1),
+    Point::new(1, -1),
+    Point::new(1, 0),
+    Point::new(1, 1),
+];
+
+impl Neighborhood {
+    fn offsets(&self) -> &'static [Point] {
+        match self {
+            Neighborhood::Four => &FOUR_NEIGHBORS,
+            Neighborhood::Eight => &EIGHT_NEIGHBORS,
+        }
+    }
+}
+
 #[derive(Debug, Parser)]
 #[command(author, version, about, long_about = None)]
 struct Args {
@@ -18,6 +54,16 @@ struct Args {
     verbose: bool,
     #[clap(short, long, value_enum)]
     mode: Mode,
+    #[clap(long, value_enum, default_value = "8")]
+    neighbors: Neighborhood,
+    /// In part 1, also print the corners of the final bounding box
+    #[clap(long)]
+    show_bounding_box: bool,
+    /// In part 1, also print the first round in which no elf moved, if there was one
+    #[clap(long)]
+    show_equilibrium_round: bool,
+    #[command(flatten)]
+    common: aoclib::CommonArgs,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -52,28 +98,38 @@ fn parse_positions(s: &str) -> Vec<Elf> {
     res
 }
 
-const NORTH: Point = Point::new(0, -1);
-const WEST: Point = Point::new(-1, 0);
-const EAST: Point = Point::new(1, 0);
-const SOUTH: Point = Point::new(0, 1);
+/// State kept between rounds so `step` doesn't have to rebuild it
+#[derive(Debug)]
+struct Occupancy {
+    /// Where every elf is, updated as they move
+    occupied: HashSet<Point>,
+    /// How many elves proposed each position this round; emptied after every round
+    proposed_count: HashMap<Point, usize>,
+}
 
-fn step(elves: &mut Vec<Elf>, directions: &[Point]) -> bool {
+impl Occupancy {
+    fn new(elves: &[Elf]) -> Self {
+        Occupancy {
+            occupied: elves.iter().map(|e| e.position).collect(),
+            proposed_count: HashMap::with_capacity(elves.len()),
+        }
+    }
+}
+
+fn step(
+    elves: &mut [Elf],
+    occupancy: &mut Occupancy,
+    directions: &[Point],
+    neighborhood: Neighborhood,
+) -> bool {
     let mut moved = false;
-    let current_positions = elves.iter().map(|e| e.position).collect::<HashSet<_>>();
+    let current_positions = &occupancy.occupied;
+    let neighbor_offsets = neighborhood.offsets();
     // phase 1: proposal
     for elf in elves.iter_mut() {
-        if [-1i64, 0, 1]
-            .into_iter()
-            .flat_map(|x| {
-                [-1i64, 0, 1].into_iter().filter_map(move |y| {
-                    if x == 0 && y == 0 {
-                        None
-                    } else {
-                        Some(Point::new(x, y))
-                    }
-                })
-            })
-            .map(|p| elf.position + p)
+        if neighbor_offsets
+            .iter()
+            .map(|p| elf.position + *p)
             .all(|p| !current_positions.contains(&p))
         {
             log::debug!("elf {} is sitting this round out", elf.id);
@@ -90,17 +146,21 @@ fn step(elves: &mut Vec<Elf>, directions: &[Point]) -> bool {
                 let check_point = elf.position + offset;
                 !current_positions.contains(&check_point)
             }) {
-                elf.proposed_position = Some(elf.position + *direction);
+                let proposal = elf.position + *direction;
+                elf.proposed_position = Some(proposal);
+                *occupancy.proposed_count.entry(proposal).or_default() += 1;
                 break;
             }
         }
     }
-    // phase 2: motion
-    let proposed_count = elves.iter().filter_map(|e| e.proposed_position).counts();
+    // phase 2: motion; a proposed position was empty, so moving into it never collides with
+    // an elf that hasn't moved yet
     for elf in elves.iter_mut() {
         if let Some(proposal) = elf.proposed_position.take() {
-            if proposed_count.get(&proposal) == Some(&1) {
+            if occupancy.proposed_count.get(&proposal) == Some(&1) {
                 log::debug!("elf {} moves {} -> {}", elf.id, elf.position, proposal);
+                occupancy.occupied.remove(&elf.position);
+                occupancy.occupied.insert(proposal);
                 elf.position = proposal;
                 moved = true;
             } else {
@@ -113,32 +173,88 @@ fn step(elves: &mut Vec<Elf>, directions: &[Point]) -> bool {
             }
         }
     }
+    occupancy.proposed_count.clear();
     moved
 }
 
-fn bounding_box(elves: &[Elf]) -> (Point, Point) {
-    let (min_x, max_x) = match elves.iter().map(|e| e.position.x).minmax() {
-        MinMaxResult::MinMax(a, b) => (a, b),
-        _ => unreachable!(),
-    };
-    let (min_y, max_y) = match elves.iter().map(|e| e.position.y).minmax() {
-        MinMaxResult::MinMax(a, b) => (a, b),
-        _ => unreachable!(),
+/// The corners of the smallest rectangle holding every elf, or None if there are no elves
+fn bounding_box(elves: &[Elf]) -> Option<(Point, Point)> {
+    DenseGrid::from_points(elves.iter().map(|e| e.position)).extent(|count| *count > 0)
+}
+
+fn empty_tiles(elves: &[Elf]) -> u64 {
+    let Some((min_bb, max_bb)) = bounding_box(elves) else {
+        return 0;
     };
-    (Point::new(min_x, min_y), Point::new(max_x, max_y))
+    let width = max_bb.x.abs_diff(min_bb.x) + 1;
+    let height = max_bb.y.abs_diff(min_bb.y) + 1;
+    width * height - elves.len() as u64
 }
 
-fn render(elves: &[Elf]) {
-    let (mut min_bb, mut max_bb) = bounding_box(elves);
-    min_bb.x -= 1;
-    min_bb.y -= 1;
-    max_bb.x += 1;
-    max_bb.y += 1;
-    let mut grid = DenseGrid::new_with(min_bb, max_bb, '.');
+/// Run a fixed number of rounds, optionally rendering after each one, and return the first
+/// round in which nobody moved
+fn run_rounds(
+    elves: &mut [Elf],
+    directions: &mut [Point],
+    neighborhood: Neighborhood,
+    rounds: usize,
+    verbose: bool,
+) -> Option<usize> {
+    let mut equilibrium = None;
+    let mut occupancy = Occupancy::new(elves);
+    let mut canvas = canvas(elves, rounds);
+    if verbose {
+        println!("=== Initial condition ===");
+        render(&mut canvas, elves).dump_with(|c| *c);
+    }
+    for round in 0..rounds {
+        if !step(elves, &mut occupancy, directions, neighborhood) && equilibrium.is_none() {
+            equilibrium = Some(round + 1);
+        }
+        directions.rotate_left(1);
+        if verbose {
+            println!("=== After round {} ===", round + 1);
+            render(&mut canvas, elves).dump_with(|c| *c);
+        }
+    }
+    equilibrium
+}
+
+/// Step until no elf moves, returning the first round in which nobody moved
+fn run_until_stable(
+    elves: &mut [Elf],
+    directions: &mut [Point],
+    neighborhood: Neighborhood,
+) -> usize {
+    let mut round = 1;
+    let mut occupancy = Occupancy::new(elves);
+    while step(elves, &mut occupancy, directions, neighborhood) {
+        round += 1;
+        directions.rotate_left(1);
+    }
+    round
+}
+
+/// A grid big enough to draw the elves on for the next `rounds` rounds; each elf moves at
+/// most one tile per round
+fn canvas(elves: &[Elf], rounds: usize) -> DenseGrid<char> {
+    let origin = Point::new(0, 0);
+    let (mut min_bb, mut max_bb) = bounding_box(elves).unwrap_or((origin, origin));
+    let margin = rounds as i64;
+    min_bb.x -= margin;
+    min_bb.y -= margin;
+    max_bb.x += margin;
+    max_bb.y += margin;
+    DenseGrid::new_with(min_bb, max_bb, '.')
+}
+
+/// Draw the elves on `canvas` and crop it down to just the elves
+fn render(canvas: &mut DenseGrid<char>, elves: &[Elf]) -> DenseGrid<char> {
+    canvas.values_mut().for_each(|c| *c = '.');
     for elf in elves {
-        grid.set(elf.position, '#');
+        canvas.set(elf.position, '#');
     }
-    grid.dump_with(|c| *c);
+    canvas.crop_to(|c| *c == '#')
 }
 
 fn main() -> anyhow::Result<()> {
@@ -153,42 +269,195 @@ fn main() -> anyhow::Result<()> {
         .format_timestamp_millis()
         .filter_level(log_level)
         .init();
-    let stdin = std::io::stdin();
-    let input = std::io::read_to_string(stdin)?;
+    let input = args.common.read_input()?;
+    let _timer = args.common.start_timer();
     let mut elves = parse_positions(&input);
 
-    let mut directions = vec![NORTH, SOUTH, WEST, EAST];
+    let mut directions = Direction::all().map(Direction::offset);
 
     if args.mode == Mode::Part1 {
-        if args.verbose {
-            println!("=== Initial condition ===");
-            render(elves.as_slice());
-        }
-        for round in 0..10 {
-            step(&mut elves, &directions);
-            directions.rotate_left(1);
-            if args.verbose {
-                println!("=== After round {} ===", round + 1);
-                render(elves.as_slice());
+        let equilibrium = run_rounds(
+            &mut elves,
+            &mut directions,
+            args.neighbors,
+            10,
+            args.verbose,
+        );
+        if args.show_equilibrium_round {
+            match equilibrium {
+                Some(round) => println!("no elf moved in round {}", round),
+                None => println!("elves still moving after 10 rounds"),
             }
         }
 
-        let (min_bb, max_bb) = bounding_box(elves.as_slice());
-        let width = max_bb.x.abs_diff(min_bb.x) + 1;
-        let height = max_bb.y.abs_diff(min_bb.y) + 1;
-        println!("{}", width * height - elves.len() as u64);
-    } else {
-        let start = std::time::Instant::now();
-        let mut round = 1;
-        loop {
-            if !step(&mut elves, &directions) {
-                break;
-            } else {
-                round += 1;
-                directions.rotate_left(1);
+        if args.show_bounding_box {
+            match bounding_box(elves.as_slice()) {
+                Some((min_bb, max_bb)) => println!("bounding box: {} -> {}", min_bb, max_bb),
+                None => println!("no elves"),
             }
         }
-        println!("{} (in {:?})", round, start.elapsed())
+        println!("{}", empty_tiles(elves.as_slice()));
+    } else {
+        let round = run_until_stable(&mut elves, &mut directions, args.neighbors);
+        println!("{}", round);
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        bounding_box, canvas, empty_tiles, parse_positions, render, run_rounds, run_until_stable,
+        step, Direction, Neighborhood, Occupancy, Point,
+    };
+    use std::collections::HashSet;
+
+    const SAMPLE: &str = "....#..
+..###.#
+#...#.#
+.#...##
+#.###..
+##.#.##
+.#..#..
+";
+
+    #[test]
+    fn test_neighborhood_offsets() {
+        assert_eq!(Neighborhood::Four.offsets().len(), 4);
+        assert_eq!(Neighborhood::Eight.offsets().len(), 8);
+    }
+
+    #[test]
+    fn test_sample_eight_neighbors() {
+        let mut elves = parse_positions(SAMPLE);
+        let mut directions = Direction::all().map(Direction::offset);
+        assert_eq!(
+            run_rounds(&mut elves, &mut directions, Neighborhood::Eight, 10, false),
+            None
+        );
+        assert_eq!(empty_tiles(&elves), 110);
+
+        let mut elves = parse_positions(SAMPLE);
+        let mut directions = Direction::all().map(Direction::offset);
+        assert_eq!(
+            run_until_stable(&mut elves, &mut directions, Neighborhood::Eight),
+            20
+        );
+    }
+
+    #[test]
+    fn test_sample_bounding_box() {
+        let mut elves = parse_positions(SAMPLE);
+        let mut directions = Direction::all().map(Direction::offset);
+        run_rounds(&mut elves, &mut directions, Neighborhood::Eight, 10, false);
+        assert_eq!(
+            bounding_box(&elves),
+            Some((Point::new(-2, -2), Point::new(9, 8)))
+        );
+        assert_eq!(bounding_box(&[]), None);
+        assert_eq!(empty_tiles(&[]), 0);
+    }
+
+    #[test]
+    fn test_render_crops_to_elves() {
+        let mut elves = parse_positions(SAMPLE);
+        let mut canvas = canvas(&elves, 10);
+        let dump = |grid: &super::DenseGrid<char>| {
+            let mut out = vec![];
+            grid.dump_to(&mut out, |c| *c).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!(dump(&render(&mut canvas, &elves)), SAMPLE);
+        let mut directions = Direction::all().map(Direction::offset);
+        run_rounds(&mut elves, &mut directions, Neighborhood::Eight, 10, false);
+        let frame = render(&mut canvas, &elves);
+        assert_eq!((frame.width(), frame.height()), (12, 11));
+        assert_eq!(dump(&frame).lines().next(), Some("......#....."));
+    }
+
+    #[test]
+    fn test_equilibrium_within_part1() {
+        let small = ".....
+..##.
+..#..
+.....
+..##.
+.....
+";
+        let mut elves = parse_positions(small);
+        let mut directions = Direction::all().map(Direction::offset);
+        let equilibrium = run_rounds(&mut elves, &mut directions, Neighborhood::Eight, 10, false);
+        assert_eq!(equilibrium, Some(4));
+
+        let mut elves = parse_positions(small);
+        let mut directions = Direction::all().map(Direction::offset);
+        assert_eq!(
+            run_until_stable(&mut elves, &mut directions, Neighborhood::Eight),
+            4
+        );
+    }
+
+    #[test]
+    fn test_small_sample_first_round() {
+        let mut elves = parse_positions(
+            ".....
+..##.
+..#..
+.....
+..##.
+.....
+",
+        );
+        let mut occupancy = Occupancy::new(&elves);
+        let directions = Direction::all().map(Direction::offset);
+        assert!(step(
+            &mut elves,
+            &mut occupancy,
+            &directions,
+            Neighborhood::Eight
+        ));
+        // the top pair move north; the middle elf and the bottom-left elf both propose the
+        // tile between them, so neither moves, while the bottom-right elf moves north
+        let positions = elves.iter().map(|e| e.position).collect::<HashSet<_>>();
+        assert_eq!(
+            positions,
+            parse_positions(
+                "..##.
+.....
+..#..
+...#.
+..#..
+.....
+",
+            )
+            .iter()
+            .map(|e| e.position)
+            .collect::<HashSet<_>>()
+        );
+        assert_eq!(occupancy.occupied, positions);
+        assert!(occupancy.proposed_count.is_empty());
+    }
+
+    #[test]
+    fn test_sample_four_neighbors() {
+        let mut elves = parse_positions(SAMPLE);
+        let mut occupancy = Occupancy::new(&elves);
+        let mut directions = Direction::all().map(Direction::offset);
+        let mut round = 1;
+        while step(&mut elves, &mut occupancy, &directions, Neighborhood::Four) {
+            assert_eq!(
+                occupancy.occupied,
+                elves.iter().map(|e| e.position).collect::<HashSet<_>>()
+            );
+            assert!(occupancy.proposed_count.is_empty());
+            round += 1;
+            directions.rotate_left(1);
+        }
+        assert_eq!(round, 3);
+
+        let mut elves = parse_positions(SAMPLE);
+        let mut directions = Direction::all().map(Direction::offset);
+        run_rounds(&mut elves, &mut directions, Neighborhood::Four, 10, false);
+        assert_eq!(empty_tiles(&elves), 68);
+    }
+}
@@ -1,8 +1,18 @@
 use clap::{Parser, ValueEnum};
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute,
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
 use itertools::Itertools;
-use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+use std::io::Write;
+use std::num::NonZeroUsize;
 
-use aoclib::{DenseGrid, Point};
+use aoclib::math::lcm;
+use aoclib::{DenseGrid, Direction, Point};
 
 #[derive(ValueEnum, Debug, PartialEq, Eq, Clone, Copy)]
 enum Mode {
@@ -15,10 +25,25 @@ enum Mode {
 struct Args {
     #[clap(short, long)]
     verbose: bool,
+    /// Don't log anything; only print the answer
+    #[clap(short, long, conflicts_with = "verbose")]
+    quiet: bool,
     #[clap(short, long)]
     dump_path: bool,
     #[clap(short, long, value_enum)]
     mode: Mode,
+    /// How many times to cross the valley (part 1 is 1, part 2 is 3)
+    #[clap(long)]
+    trips: Option<NonZeroUsize>,
+    /// Step through the path one minute at a time, waiting for a key between frames
+    #[clap(long, conflicts_with = "quiet")]
+    interactive: bool,
+    /// Prune any state whose position and point in the blizzard cycle were already reached
+    /// earlier in the same leg
+    #[clap(long)]
+    period_skip: bool,
+    #[command(flatten)]
+    common: aoclib::CommonArgs,
 }
 
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
@@ -45,6 +70,11 @@ struct Map {
 
 impl Map {
     fn dump(&self, pos: Point) {
+        self.render(pos).dump_with(|c| *c as char);
+    }
+
+    /// Draw the blizzards, and the expedition at `pos` if it's inside the valley
+    fn render(&self, pos: Point) -> DenseGrid<u8> {
         let mut g = DenseGrid::new_with(
             Point::new(0, 0),
             Point::new(self.width - 1, self.height - 1),
@@ -77,7 +107,7 @@ impl Map {
             }
             g.set(pos, b'E');
         }
-        g.dump_with(|c| *c as char);
+        g
     }
 
     fn step(&self) -> Map {
@@ -105,13 +135,13 @@ impl Map {
         next
     }
 
-    fn can_move(&self, position: Point) -> bool {
-        if position.x < 0 || position.x >= self.width || position.y < 0 || position.y >= self.height
-        {
-            false
-        } else {
-            !self.blizzards.iter().any(|b| b.position == position)
-        }
+    fn contains(&self, position: Point) -> bool {
+        position.x >= 0 && position.x < self.width && position.y >= 0 && position.y < self.height
+    }
+
+    /// How many steps it takes for every blizzard to be back where it started
+    fn period(&self) -> usize {
+        lcm(self.width as usize, self.height as usize)
     }
 }
 
@@ -129,7 +159,7 @@ fn parse_map(s: &str) -> (Map, Point, Point) {
         - 1;
     let end_x = s
         .split('\n')
-        .last()
+        .next_back()
         .unwrap()
         .chars()
         .position(|p| p == '.')
@@ -177,72 +207,113 @@ fn parse_map(s: &str) -> (Map, Point, Point) {
 
 #[derive(Debug)]
 struct Memo {
-    maps_by_step: BTreeMap<usize, Map>,
+    initial: Map,
+    /// Where the blizzards are at each step of their cycle
+    occupied: Vec<HashSet<Point>>,
     seen: HashSet<(Point, usize)>,
+    period_skip: bool,
 }
 
 impl Memo {
-    fn ensure_map(&mut self, timestamp: usize) {
-        if self.maps_by_step.contains_key(&timestamp) {
-            return;
+    fn new(initial: Map) -> Self {
+        let mut occupied = Vec::with_capacity(initial.period());
+        let mut map = initial.clone();
+        for _ in 0..initial.period() {
+            occupied.push(map.blizzards.iter().map(|b| b.position).collect());
+            map = map.step();
         }
-        let (max_ts, max_map) = self.maps_by_step.last_key_value().unwrap();
-        let max_ts = *max_ts;
-        let mut max_map = max_map.clone();
-        for ts in (max_ts + 1)..=timestamp {
-            max_map = max_map.step();
-            self.maps_by_step.insert(ts, max_map.clone());
+        Self {
+            initial,
+            occupied,
+            seen: HashSet::new(),
+            period_skip: false,
         }
     }
 
-    fn dump_with_path(&mut self, path: &[(usize, Point)]) {
+    /// Whether `simulate` should also treat states a whole number of blizzard periods apart as
+    /// the same; arriving somewhere later in the same phase can never be faster
+    fn with_period_skip(mut self, period_skip: bool) -> Self {
+        self.period_skip = period_skip;
+        self
+    }
+
+    fn period(&self) -> usize {
+        self.occupied.len()
+    }
+
+    /// How many distinct (position, timestamp) states have been searched so far
+    fn states_explored(&self) -> usize {
+        self.seen.len()
+    }
+
+    /// The full map (with blizzard directions) at `timestamp`; only needed for drawing
+    fn map_at(&self, timestamp: usize) -> Map {
+        (0..timestamp % self.period()).fold(self.initial.clone(), |map, _| map.step())
+    }
+
+    fn can_move(&self, position: Point, timestamp: usize) -> bool {
+        self.initial.contains(position)
+            && !self.occupied[timestamp % self.period()].contains(&position)
+    }
+
+    /// One frame of text per step of the path, for stepping through it interactively
+    fn frames(&self, path: &[(usize, Point)]) -> Vec<String> {
+        path.iter()
+            .map(|(ts, position)| {
+                let mut frame = format!("TS={}, POS={}\n", ts, position).into_bytes();
+                self.map_at(*ts)
+                    .render(*position)
+                    .dump_to(&mut frame, |c| *c as char)
+                    .unwrap();
+                String::from_utf8(frame).unwrap()
+            })
+            .collect()
+    }
+
+    fn dump_with_path(&self, path: &[(usize, Point)]) {
         for (ts, position) in path.iter() {
-            self.ensure_map(*ts);
-            let map = self.maps_by_step.get(&ts).unwrap();
             println!("TS={}, POS={}", ts, position);
-            map.dump(*position);
+            self.map_at(*ts).dump(*position);
             println!();
         }
     }
 }
 
-const SOUTH: Point = Point::new(0, 1);
-const EAST: Point = Point::new(1, 0);
-const WEST: Point = Point::new(-1, 0);
-const NORTH: Point = Point::new(0, -1);
+const SOUTH: Point = Direction::South.offset();
+const EAST: Point = Direction::East.offset();
+const WEST: Point = Direction::West.offset();
+const NORTH: Point = Direction::North.offset();
 
 trait MaybePath: std::fmt::Debug {
-    fn empty() -> Self;
     fn with(&self, ts: usize, position: Point) -> Self;
-    fn dump_with(&self, _memo: &mut Memo) {}
+    fn dump_with(&self, _memo: &Memo) {}
+    fn frames(&self, _memo: &Memo) -> Vec<String> {
+        vec![]
+    }
     fn end_ts(&self) -> usize;
 }
 
 impl MaybePath for Vec<(usize, Point)> {
-    fn empty() -> Self {
-        vec![]
-    }
-
     fn with(&self, ts: usize, position: Point) -> Self {
         let mut new = self.clone();
         new.push((ts, position));
         new
     }
 
-    fn dump_with(&self, memo: &mut Memo) {
+    fn dump_with(&self, memo: &Memo) {
         memo.dump_with_path(self)
     }
 
+    fn frames(&self, memo: &Memo) -> Vec<String> {
+        memo.frames(self)
+    }
+
     fn end_ts(&self) -> usize {
         self.iter().last().map(|p| p.0).unwrap_or(0)
     }
 }
 
 impl MaybePath for usize {
-    fn empty() -> Self {
-        0
-    }
-
     fn with(&self, ts: usize, _position: Point) -> Self {
         ts
     }
@@ -252,6 +323,8 @@ impl MaybePath for usize {
     }
 }
 
+/// A* search for the fastest way from `start_coordinate` to `end_coordinate`. You can only
+/// move one cell per minute, so the manhattan distance to the end never overestimates.
 fn simulate<P: MaybePath>(
     memo: &mut Memo,
     start_coordinate: Point,
@@ -259,32 +332,49 @@ fn simulate<P: MaybePath>(
     end_coordinate: Point,
     empty_path: &P,
 ) -> P {
-    let mut queue = VecDeque::new();
-    let mut max_ts = 0;
-    queue.push_back((
+    let estimate = |position: Point, timestamp: usize| {
+        timestamp + position.manhattan_distance_to(end_coordinate)
+    };
+    // the heap only holds estimates and indices, since paths aren't comparable
+    let mut candidates = vec![Some((
         start_coordinate,
         empty_path.with(start_ts, start_coordinate),
-    ));
-    while let Some((position, path)) = queue.pop_front() {
+    ))];
+    let mut heap = BinaryHeap::new();
+    heap.push(Reverse((estimate(start_coordinate, start_ts), 0)));
+    let mut max_ts = 0;
+    // keyed on the phase of the blizzard cycle, so only valid within this leg
+    let mut phases_seen = HashSet::new();
+    while let Some(Reverse((_, index))) = heap.pop() {
+        let (position, path) = candidates[index].take().unwrap();
         let timestamp = path.end_ts();
         log::debug!("considering {} at {}", position, timestamp);
         max_ts = std::cmp::max(max_ts, timestamp);
         if !memo.seen.insert((position, timestamp)) {
             continue;
         }
-        memo.ensure_map(timestamp + 1);
-        let map = memo.maps_by_step.get(&(timestamp + 1)).unwrap();
-        for offset in &[SOUTH, NORTH, WEST, EAST] {
-            let candidate = position + *offset;
+        if memo.period_skip && !phases_seen.insert((position, timestamp % memo.period())) {
+            continue;
+        }
+        let mut next = vec![];
+        for direction in Direction::all() {
+            let candidate = position + direction.offset();
             if candidate == end_coordinate {
                 return path.with(timestamp + 1, end_coordinate);
             }
-            if map.can_move(candidate) {
-                queue.push_back((candidate, path.with(timestamp + 1, candidate)));
+            if memo.can_move(candidate, timestamp + 1) {
+                next.push(candidate);
             }
         }
-        if map.can_move(position) || position == start_coordinate {
-            queue.push_back((position, path.with(timestamp + 1, position)));
+        if memo.can_move(position, timestamp + 1) || position == start_coordinate {
+            next.push(position);
+        }
+        for candidate in next {
+            heap.push(Reverse((
+                estimate(candidate, timestamp + 1),
+                candidates.len(),
+            )));
+            candidates.push(Some((candidate, path.with(timestamp + 1, candidate))));
         }
     }
     panic!("ran out of moves at {}", max_ts);
@@ -292,7 +382,9 @@ fn simulate<P: MaybePath>(
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
-    let log_level = if args.verbose {
+    let log_level = if args.quiet {
+        log::LevelFilter::Off
+    } else if args.verbose {
         log::LevelFilter::Debug
     } else {
         log::LevelFilter::Info
@@ -302,21 +394,79 @@ fn main() -> anyhow::Result<()> {
         .format_timestamp_millis()
         .filter_level(log_level)
         .init();
-    let stdin = std::io::stdin();
-    let input = std::io::read_to_string(stdin)?;
+    let input = args.common.read_input()?;
+    let _timer = args.common.start_timer();
     let (first_map, start_coordinate, end_coordinate) = parse_map(&input);
-    let mut maps_by_step = BTreeMap::new();
-    maps_by_step.insert(0, first_map);
-    let memo = Memo {
-        maps_by_step,
-        seen: HashSet::new(),
-    };
-    if args.verbose {
-        run_rest(start_coordinate, end_coordinate, memo, vec![], args);
+    let memo = Memo::new(first_map).with_period_skip(args.period_skip);
+    if args.verbose || args.interactive {
+        run_rest(start_coordinate, end_coordinate, memo, vec![], args)
     } else {
-        run_rest(start_coordinate, end_coordinate, memo, 0, args);
+        run_rest(start_coordinate, end_coordinate, memo, 0, args)
     }
-    Ok(())
+}
+
+/// Show one frame at a time, waiting for a key between them; q or escape stops early
+fn step_through(frames: &[String]) -> anyhow::Result<()> {
+    let mut stdout = std::io::stdout();
+    execute!(stdout, EnterAlternateScreen, Hide)?;
+    terminal::enable_raw_mode()?;
+    let result = (|| -> anyhow::Result<()> {
+        for (i, frame) in frames.iter().enumerate() {
+            execute!(stdout, Clear(ClearType::All), MoveTo(0, 0))?;
+            // raw mode doesn't return the cursor to the start of the line by itself
+            for line in frame.lines() {
+                write!(stdout, "{}\r\n", line)?;
+            }
+            write!(
+                stdout,
+                "[ frame {} of {}; any key for the next, q to quit ]",
+                i + 1,
+                frames.len()
+            )?;
+            stdout.flush()?;
+            loop {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind != KeyEventKind::Press {
+                        continue;
+                    }
+                    if matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                        return Ok(());
+                    }
+                    break;
+                }
+            }
+        }
+        Ok(())
+    })();
+    terminal::disable_raw_mode()?;
+    execute!(stdout, Show, LeaveAlternateScreen)?;
+    result
+}
+
+/// Go back and forth between the start and the end `trips` times, returning the path taken
+/// on each leg; every leg starts when the previous one finished
+fn run_trips<P: MaybePath>(
+    memo: &mut Memo,
+    start_coordinate: Point,
+    end_coordinate: Point,
+    trips: usize,
+    empty_path: &P,
+) -> Vec<P> {
+    let mut legs: Vec<P> = Vec::with_capacity(trips);
+    let (mut from, mut to) = (start_coordinate, end_coordinate);
+    for leg in 0..trips {
+        let start_ts = legs.last().map(|leg| leg.end_ts()).unwrap_or(0);
+        let explored = memo.states_explored();
+        legs.push(simulate(memo, from, start_ts, to, empty_path));
+        log::debug!(
+            "leg {} explored {} distinct states ({} in total)",
+            leg + 1,
+            memo.states_explored() - explored,
+            memo.states_explored()
+        );
+        std::mem::swap(&mut from, &mut to);
+    }
+    legs
 }
 
 fn run_rest<P: MaybePath>(
@@ -325,40 +475,151 @@ fn run_rest<P: MaybePath>(
     mut memo: Memo,
     empty_path: P,
     args: Args,
-) {
-    let start = std::time::Instant::now();
-    let best = if args.mode == Mode::Part1 {
-        let path = simulate(&mut memo, start_coordinate, 0, end_coordinate, &empty_path);
-        if args.dump_path {
-            path.dump_with(&mut memo);
-        }
-        path
-    } else {
-        let first = simulate(&mut memo, start_coordinate, 0, end_coordinate, &empty_path);
-        if args.dump_path {
-            first.dump_with(&mut memo);
-        }
-        let second = simulate(
-            &mut memo,
-            end_coordinate,
-            first.end_ts(),
-            start_coordinate,
-            &empty_path,
-        );
-        if args.dump_path {
-            second.dump_with(&mut memo);
+) -> anyhow::Result<()> {
+    let trips = args
+        .trips
+        .map(NonZeroUsize::get)
+        .unwrap_or(match args.mode {
+            Mode::Part1 => 1,
+            Mode::Part2 => 3,
+        });
+    let legs = run_trips(
+        &mut memo,
+        start_coordinate,
+        end_coordinate,
+        trips,
+        &empty_path,
+    );
+    if args.dump_path {
+        for leg in legs.iter() {
+            leg.dump_with(&memo);
         }
-        let third = simulate(
-            &mut memo,
-            start_coordinate,
-            second.end_ts(),
-            end_coordinate,
-            &empty_path,
+    }
+    if args.interactive {
+        let frames = legs
+            .iter()
+            .flat_map(|leg| leg.frames(&memo))
+            .collect::<Vec<_>>();
+        step_through(&frames)?;
+    }
+    let end_ts = legs.last().map(|leg| leg.end_ts()).unwrap_or(0);
+    println!("{}", end_ts);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_map, run_trips, simulate, Args, MaybePath, Memo};
+    use clap::Parser;
+
+    const SAMPLE: &str = "#.######
+#>>.<^<#
+#.<..<<#
+#>v.><>#
+#<^v^^>#
+######.#
+";
+
+    #[test]
+    fn test_period() {
+        let (map, _, _) = parse_map(SAMPLE);
+        let memo = Memo::new(map.clone());
+        assert_eq!(memo.period(), 12);
+        assert_eq!(memo.map_at(12).blizzards, map.blizzards);
+        let stepped = (0..12).fold(map.clone(), |m, _| m.step());
+        assert_eq!(stepped.blizzards, map.blizzards);
+        assert_ne!(memo.occupied[1], memo.occupied[0]);
+    }
+
+    #[test]
+    fn test_sample() {
+        let (map, start, end) = parse_map(SAMPLE);
+        let mut memo = Memo::new(map);
+        let first = simulate(&mut memo, start, 0, end, &0usize);
+        assert_eq!(first.end_ts(), 18);
+        let second = simulate(&mut memo, end, first, start, &0usize);
+        let third = simulate(&mut memo, start, second, end, &0usize);
+        assert_eq!(third, 54);
+    }
+
+    #[test]
+    fn test_optimal_times() {
+        // the times a breadth-first search finds on the sample: 18 minutes there, 23 back,
+        // and 13 there again
+        let (map, start, end) = parse_map(SAMPLE);
+        let mut memo = Memo::new(map);
+        let first = simulate(&mut memo, start, 0, end, &0usize);
+        let second = simulate(&mut memo, end, first, start, &0usize);
+        let third = simulate(&mut memo, start, second, end, &0usize);
+        assert_eq!(vec![first, second, third], vec![18, 41, 54]);
+
+        // with no blizzards, every trip is just the manhattan distance between the openings
+        let (map, start, end) = parse_map("#.###\n#...#\n#...#\n###.#\n");
+        let mut memo = Memo::new(map);
+        assert_eq!(run_trips(&mut memo, start, end, 3, &0), vec![5, 10, 15]);
+    }
+
+    #[test]
+    fn test_trips() {
+        let (map, start, end) = parse_map(SAMPLE);
+        let mut memo = Memo::new(map.clone());
+        let there_and_back = run_trips(&mut memo, start, end, 2, &0);
+        let mut memo = Memo::new(map.clone());
+        let part2 = run_trips(&mut memo, start, end, 3, &0);
+        assert_eq!(there_and_back, vec![18, 41]);
+        assert_eq!(part2[..2], there_and_back[..]);
+        assert_eq!(part2[2], 54);
+        let mut memo = Memo::new(map);
+        assert!(run_trips(&mut memo, start, end, 0, &0).is_empty());
+    }
+
+    #[test]
+    fn test_states_explored() {
+        let (map, start, end) = parse_map(SAMPLE);
+        let mut memo = Memo::new(map);
+        assert_eq!(memo.states_explored(), 0);
+        let first = run_trips(&mut memo, start, end, 1, &0);
+        let after_first = memo.states_explored();
+        assert!(after_first > 0);
+        assert_eq!(first, vec![18]);
+        assert_eq!(simulate(&mut memo, end, 18, start, &0), 41);
+        assert!(memo.states_explored() > after_first);
+    }
+
+    #[test]
+    fn test_frames() {
+        let (map, start, end) = parse_map(SAMPLE);
+        let mut memo = Memo::new(map);
+        let legs = run_trips(&mut memo, start, end, 2, &vec![]);
+        let first = legs[0].frames(&memo);
+        // one frame for the start plus one for every minute
+        assert_eq!(first.len(), 19);
+        assert!(first[0].starts_with("TS=0, POS=(0, -1)\n"));
+        assert!(first[1].starts_with("TS=1, POS=(0, 0)\n"));
+        assert!(first[1].contains('E'));
+        assert!(first[18].starts_with("TS=18, POS=(5, 4)\n"));
+        // a frame is its header plus a row per line of the valley
+        assert!(first.iter().all(|f| f.lines().count() == 5));
+        let second = legs[1].frames(&memo);
+        assert_eq!(second.len(), 41 - 18 + 1);
+        assert!(0usize.frames(&memo).is_empty());
+    }
+
+    #[test]
+    fn test_period_skip() {
+        let (map, start, end) = parse_map(SAMPLE);
+        let mut plain = Memo::new(map.clone());
+        let mut skipping = Memo::new(map).with_period_skip(true);
+        assert_eq!(
+            run_trips(&mut skipping, start, end, 3, &0),
+            run_trips(&mut plain, start, end, 3, &0)
         );
-        if args.dump_path {
-            third.dump_with(&mut memo);
-        }
-        third
-    };
-    println!("{} (in {:?})", best.end_ts(), start.elapsed());
+        assert!(skipping.states_explored() <= plain.states_explored());
+    }
+
+    #[test]
+    fn test_zero_trips_rejected() {
+        assert!(Args::try_parse_from(["aoc24", "-m", "part1", "--trips", "0"]).is_err());
+        assert!(Args::try_parse_from(["aoc24", "-m", "part1", "--trips", "1"]).is_ok());
+    }
 }
@@ -0,0 +1,138 @@
+use clap::{Parser, ValueEnum};
+use itertools::Itertools;
+
+#[derive(ValueEnum, Debug, PartialEq, Eq, Clone, Copy)]
+enum Mode {
+    /// Find the item in both halves of each rucksack
+    Part1,
+    /// Find the item common to each group of three rucksacks
+    Part2,
+}
+
+#[derive(Debug, Parser)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[arg(short, long, value_enum)]
+    mode: Mode,
+    #[command(flatten)]
+    common: aoclib::CommonArgs,
+}
+
+trait Priority {
+    fn priority(&self) -> u32;
+}
+
+impl Priority for char {
+    fn priority(&self) -> u32 {
+        if self.is_ascii_lowercase() {
+            (*self as u32) - ('a' as u32) + 1
+        } else if self.is_ascii_uppercase() {
+            (*self as u32) - ('A' as u32) + 27
+        } else {
+            panic!("what is {:?}", self);
+        }
+    }
+}
+
+/// Which item types are in `items`, as a bitmask with bit `n` set for priority `n`
+fn item_mask(items: &str) -> u64 {
+    items.chars().fold(0, |mask, c| mask | (1 << c.priority()))
+}
+
+/// The priority of the item that shows up in every one of `groups`
+fn common_priority(groups: &[&str]) -> u32 {
+    let common = groups
+        .iter()
+        .map(|g| item_mask(g))
+        .fold(u64::MAX, |a, b| a & b);
+    assert_ne!(common, 0, "no item in common in {:?}", groups);
+    common.trailing_zeros()
+}
+
+fn total_priority<I: Iterator<Item = String>>(lines: I, mode: Mode) -> u32 {
+    match mode {
+        Mode::Part1 => lines
+            .map(|line| {
+                let midpoint = line.len() / 2;
+                let (cpt1, cpt2) = line.split_at(midpoint);
+                common_priority(&[cpt1, cpt2])
+            })
+            .sum(),
+        Mode::Part2 => lines
+            .tuples()
+            .map(|(elf1, elf2, elf3)| common_priority(&[&elf1, &elf2, &elf3]))
+            .sum(),
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let input = args.common.read_input()?;
+    let _timer = args.common.start_timer();
+    let res = total_priority(input.lines().map(str::to_owned), args.mode);
+    println!("{}", res);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{common_priority, total_priority, Mode, Priority};
+    use std::collections::HashSet;
+
+    const SAMPLE: &str = "vJrwpWtwJgWrhcsFMMfFFhFp
+jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+PmmdzqPrVvPwwTWBwg
+wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
+ttgJtRGJQctTZtZT
+CrZsJsPPZsGzwwsLwLmpwMDw
+";
+
+    #[test]
+    fn test_sample() {
+        let lines = || SAMPLE.lines().map(String::from);
+        assert_eq!(total_priority(lines(), Mode::Part1), 157);
+        assert_eq!(total_priority(lines(), Mode::Part2), 70);
+    }
+
+    #[test]
+    fn test_common_priority() {
+        // the puzzle's worked examples: p, L, P, v, t, s, then the badges r and Z
+        let lines = SAMPLE.lines().collect::<Vec<_>>();
+        let compartments = lines
+            .iter()
+            .map(|line| {
+                let (cpt1, cpt2) = line.split_at(line.len() / 2);
+                common_priority(&[cpt1, cpt2])
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(compartments, vec![16, 38, 42, 22, 20, 19]);
+        let badges = lines.chunks(3).map(common_priority).collect::<Vec<_>>();
+        assert_eq!(badges, vec![18, 52]);
+
+        assert_eq!(common_priority(&["a", "a"]), 1);
+        assert_eq!(common_priority(&["zA", "Az"]), 26);
+        assert_eq!(common_priority(&["azAZ", "Z"]), 52);
+        // with more than one item in common, the lowest priority wins
+        assert_eq!(common_priority(&["azAZ", "Zz"]), 26);
+    }
+
+    #[test]
+    fn test_matches_sets() {
+        let by_sets = |groups: &[&str]| {
+            let sets = groups
+                .iter()
+                .map(|g| g.chars().collect::<HashSet<_>>())
+                .collect::<Vec<_>>();
+            let common = sets[1..].iter().fold(sets[0].clone(), |a, b| &a & b);
+            common.iter().map(|c| c.priority()).min().unwrap()
+        };
+        let lines = SAMPLE.lines().collect::<Vec<_>>();
+        for line in lines.iter() {
+            let (cpt1, cpt2) = line.split_at(line.len() / 2);
+            assert_eq!(common_priority(&[cpt1, cpt2]), by_sets(&[cpt1, cpt2]));
+        }
+        for group in lines.chunks(3) {
+            assert_eq!(common_priority(group), by_sets(group), "{:?}", group);
+        }
+    }
+}
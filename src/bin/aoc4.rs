@@ -1,4 +1,3 @@
-use std::io::BufRead;
 use std::ops::RangeInclusive;
 use std::str::FromStr;
 
@@ -28,11 +27,7 @@ impl Assignment {
     }
 
     fn overlaps(&self, other: &Self) -> bool {
-        if self.0.contains(other.0.start()) || self.0.contains(other.0.end()) {
-            true
-        } else {
-            other.0.start() <= self.0.start() && other.0.end() >= self.0.start()
-        }
+        self.0.start() <= other.0.end() && other.0.start() <= self.0.end()
     }
 }
 
@@ -45,34 +40,65 @@ enum Mode {
 #[derive(Debug, Parser)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    #[arg(short, long, value_enum)]
-    mode: Mode,
+    #[arg(short, long, value_enum, required_unless_present = "both")]
+    mode: Option<Mode>,
+    /// Print the part 1 and part 2 counts, in that order
+    #[arg(long, conflicts_with = "mode")]
+    both: bool,
+    #[command(flatten)]
+    common: aoclib::CommonArgs,
 }
 
-fn main() {
-    let args = Args::parse();
-    let stdin = std::io::stdin();
-    let handle = stdin.lock();
-    let rv = handle
-        .lines()
-        .filter_map(Result::ok)
+/// Count the pairs where one assignment fully contains the other, and the pairs that overlap
+fn count_pairs<I: Iterator<Item = String>>(lines: I) -> (usize, usize) {
+    lines
         .map(|line| {
             let (first, second) = line.split_once(',').unwrap();
             let first = first.parse::<Assignment>().unwrap();
             let second = second.parse::<Assignment>().unwrap();
             (first, second)
         })
-        .filter(|(first, second)| match args.mode {
-            Mode::Part1 => first.fully_contains(second) || second.fully_contains(first),
-            Mode::Part2 => first.overlaps(second),
+        .fold((0, 0), |(contained, overlapping), (first, second)| {
+            (
+                contained
+                    + usize::from(first.fully_contains(&second) || second.fully_contains(&first)),
+                overlapping + usize::from(first.overlaps(&second)),
+            )
         })
-        .count();
-    println!("{}", rv);
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let input = args.common.read_input()?;
+    let _timer = args.common.start_timer();
+    let (contained, overlapping) = count_pairs(input.lines().map(str::to_owned));
+    match args.mode {
+        Some(Mode::Part1) => println!("{}", contained),
+        Some(Mode::Part2) => println!("{}", overlapping),
+        None => {
+            println!("{}", contained);
+            println!("{}", overlapping);
+        }
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Assignment;
+    use super::{count_pairs, Assignment};
+
+    const SAMPLE: &str = "2-4,6-8
+2-3,4-5
+5-7,7-9
+2-8,3-7
+6-6,4-6
+2-6,4-8
+";
+
+    #[test]
+    fn test_sample_both() {
+        assert_eq!(count_pairs(SAMPLE.lines().map(String::from)), (2, 4));
+    }
 
     #[test]
     fn test_assignment_overlaps() {
@@ -80,4 +106,27 @@ mod tests {
         assert!(Assignment::from(10..=10).overlaps(&Assignment::from(0..=20)));
         assert!(Assignment::from(10..=10).overlaps(&Assignment::from(0..=10)));
     }
+
+    #[test]
+    fn test_assignment_overlaps_symmetric() {
+        let cases = [
+            // touching
+            (0..=5, 5..=10, true),
+            (10..=10, 0..=10, true),
+            // disjoint
+            (0..=4, 5..=10, false),
+            (2..=4, 6..=8, false),
+            // nested
+            (10..=10, 0..=20, true),
+            (2..=8, 3..=7, true),
+            // partial
+            (5..=7, 7..=9, true),
+            (2..=6, 4..=8, true),
+        ];
+        for (a, b, expected) in cases {
+            let (a, b) = (Assignment::from(a), Assignment::from(b));
+            assert_eq!(a.overlaps(&b), expected, "{:?} {:?}", a, b);
+            assert_eq!(a.overlaps(&b), b.overlaps(&a), "{:?} {:?}", a, b);
+        }
+    }
 }
@@ -1,6 +1,12 @@
-use std::io::BufRead;
+use std::ops::Range;
 
 use clap::{Parser, ValueEnum};
+use nom::{
+    bytes::complete::tag,
+    combinator::{all_consuming, map, verify},
+    sequence::{preceded, tuple},
+    IResult,
+};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 struct Crate(char);
@@ -20,6 +26,44 @@ struct Command {
     dest_stack: usize,
 }
 
+fn parse_command(s: &str) -> IResult<&str, Command> {
+    // stacks are numbered from 1
+    let stack = || verify(nom::character::complete::u64, |n| *n > 0);
+    map(
+        tuple((
+            preceded(tag("move "), nom::character::complete::u32),
+            preceded(tag(" from "), stack()),
+            preceded(tag(" to "), stack()),
+        )),
+        |(num_crates, source_stack, dest_stack)| Command {
+            num_crates,
+            source_stack: source_stack as usize,
+            dest_stack: dest_stack as usize,
+        },
+    )(s)
+}
+
+/// Parse the row of stack numbers under the diagram into each number and the columns it
+/// spans, or None if this isn't that row
+fn parse_stack_labels(line: &str) -> Option<Vec<(usize, Range<usize>)>> {
+    if line.trim().is_empty() || !line.chars().all(|c| c.is_ascii_digit() || c == ' ') {
+        return None;
+    }
+    let mut labels = Vec::new();
+    let mut start = None;
+    for (index, chr) in line
+        .char_indices()
+        .chain(std::iter::once((line.len(), ' ')))
+    {
+        if chr.is_ascii_digit() {
+            start.get_or_insert(index);
+        } else if let Some(start) = start.take() {
+            labels.push((line[start..index].parse().ok()?, start..index));
+        }
+    }
+    Some(labels)
+}
+
 #[derive(Debug)]
 struct Scene {
     stacks: Vec<Stack>,
@@ -27,48 +71,57 @@ struct Scene {
 }
 
 impl Scene {
-    fn parse<I: Iterator<Item = String>>(lines: I) -> anyhow::Result<Self> {
-        let mut stacks: Vec<Stack> = Vec::new();
+    /// Parse the crate diagram and moves; with `bottom_up`, the first row of crates in the
+    /// diagram is the bottom of each stack rather than the top
+    fn parse<I: Iterator<Item = String>>(lines: I, bottom_up: bool) -> anyhow::Result<Self> {
+        let mut diagram = Vec::new();
         let mut commands = Vec::new();
         for line in lines {
-            if line.trim_start().starts_with('[') {
-                for (index, _) in line.match_indices('[') {
-                    let column = index / 4;
-                    if column >= stacks.len() {
-                        for _ in stacks.len()..=column {
-                            stacks.push(Stack::new());
-                        }
-                    }
-                    let item = line
-                        .chars()
-                        .nth(index + 1)
-                        .map(Crate::new)
-                        .ok_or_else(|| anyhow::anyhow!("invalid column definition"))?;
-                    stacks[column].push(item);
-                }
-            } else if line.starts_with("move") {
-                let mut parts = line.split(' ');
-                let num_crates = parts
-                    .nth(1)
-                    .ok_or_else(|| anyhow::anyhow!("invalid move line {}", line))
-                    .and_then(|p| p.parse().map_err(anyhow::Error::new))?;
-                let source_stack = parts
-                    .nth(1)
-                    .ok_or_else(|| anyhow::anyhow!("invalid move line {}", line))
-                    .and_then(|p| p.parse::<usize>().map_err(anyhow::Error::new))?;
-                let dest_stack = parts
-                    .nth(1)
-                    .ok_or_else(|| anyhow::anyhow!("invalid move line {}", line))
-                    .and_then(|p| p.parse::<usize>().map_err(anyhow::Error::new))?;
-                commands.push(Command {
-                    num_crates,
-                    source_stack,
-                    dest_stack,
-                });
+            if line.trim_start().starts_with("move") {
+                let (_, command) = all_consuming(parse_command)(line.trim())
+                    .map_err(|e| anyhow::anyhow!("invalid move line {:?}: {:?}", line, e))?;
+                commands.push(command);
+            } else if !line.trim().is_empty() {
+                diagram.push(line);
             }
         }
-        for stack in stacks.iter_mut() {
-            stack.reverse();
+        let (label_row, labels) = diagram
+            .iter()
+            .enumerate()
+            .find_map(|(i, line)| parse_stack_labels(line).map(|labels| (i, labels)))
+            .ok_or_else(|| anyhow::anyhow!("no row of stack numbers"))?;
+        for (index, (label, _)) in labels.iter().enumerate() {
+            if *label != index + 1 {
+                anyhow::bail!("stack {} is labeled {}", index + 1, label);
+            }
+        }
+        let mut stacks: Vec<Stack> = vec![Stack::new(); labels.len()];
+        for line in diagram
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != label_row)
+            .map(|(_, line)| line)
+        {
+            for (index, _) in line.match_indices('[') {
+                // a crate belongs to the stack whose number is underneath any part of it
+                let column = labels
+                    .iter()
+                    .position(|(_, span)| span.start <= index + 2 && index < span.end)
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("crate at column {} is not above a stack number", index)
+                    })?;
+                let item = line[index + 1..]
+                    .chars()
+                    .next()
+                    .map(Crate::new)
+                    .ok_or_else(|| anyhow::anyhow!("invalid column definition"))?;
+                stacks[column].push(item);
+            }
+        }
+        if !bottom_up {
+            for stack in stacks.iter_mut() {
+                stack.reverse();
+            }
         }
         Ok(Self { stacks, commands })
     }
@@ -110,6 +163,15 @@ impl Scene {
         }
         Ok(())
     }
+
+    fn top_crates(&self) -> String {
+        let empty_crate = Crate::new(' ');
+        self.stacks
+            .iter()
+            .map(|s| s.last().cloned().unwrap_or(empty_crate))
+            .map(|c| c.0)
+            .collect()
+    }
 }
 
 #[derive(ValueEnum, Debug, PartialEq, Eq, Clone, Copy)]
@@ -123,20 +185,89 @@ enum Mode {
 struct Args {
     #[arg(short, long, value_enum)]
     mode: Mode,
+    /// The crate diagram lists the bottom of each stack first
+    #[arg(long)]
+    bottom_up: bool,
+    #[command(flatten)]
+    common: aoclib::CommonArgs,
 }
 
-fn main() {
+fn main() -> anyhow::Result<()> {
     let args = Args::parse();
-    let stdin = std::io::stdin();
-    let handle = stdin.lock();
-    let mut scene = Scene::parse(handle.lines().filter_map(Result::ok)).unwrap();
-    scene.run(args.mode).unwrap();
-    let empty_crate = Crate::new(' ');
-    let output: String = scene
-        .stacks
-        .iter()
-        .map(|s| s.last().cloned().unwrap_or(empty_crate))
-        .map(|c| c.0)
-        .collect();
-    println!("{}", output);
+    let input = args.common.read_input()?;
+    let _timer = args.common.start_timer();
+    let mut scene = Scene::parse(input.lines().map(str::to_owned), args.bottom_up)?;
+    scene.run(args.mode)?;
+    println!("{}", scene.top_crates());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_command, Mode, Scene};
+
+    const SAMPLE: &str = "    [D]    
+[N] [C]    
+[Z] [M] [P]
+ 1   2   3 
+
+move 1 from 2 to 1
+move 3 from 1 to 3
+move 2 from 2 to 1
+move 1 from 1 to 2
+";
+
+    const SAMPLE_BOTTOM_UP: &str = " 1   2   3 
+[Z] [M] [P]
+[N] [C]    
+    [D]    
+
+move 1 from 2 to 1
+move 3 from 1 to 3
+move 2 from 2 to 1
+move 1 from 1 to 2
+";
+
+    fn top_crates(input: &str, bottom_up: bool, mode: Mode) -> String {
+        let mut scene = Scene::parse(input.lines().map(String::from), bottom_up).unwrap();
+        scene.run(mode).unwrap();
+        scene.top_crates()
+    }
+
+    #[test]
+    fn test_bottom_up() {
+        for (mode, expected) in [(Mode::Part1, "CMZ"), (Mode::Part2, "MCD")] {
+            assert_eq!(top_crates(SAMPLE, false, mode), expected);
+            assert_eq!(top_crates(SAMPLE_BOTTOM_UP, true, mode), expected);
+        }
+    }
+
+    #[test]
+    fn test_parse_command() {
+        let (remaining, command) = parse_command("move 12 from 10 to 3").unwrap();
+        assert!(remaining.is_empty());
+        assert_eq!(command.num_crates, 12);
+        assert_eq!(command.source_stack, 10);
+        assert_eq!(command.dest_stack, 3);
+        assert!(parse_command("move 1 from two to 3").is_err());
+        assert!(parse_command("move 1 from 0 to 1").is_err());
+        assert!(parse_command("move 1 from 1 to 0").is_err());
+    }
+
+    #[test]
+    fn test_two_digit_stacks() {
+        let crates = ('A'..='J')
+            .map(|c| format!("[{}]", c))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let labels = (1..=10)
+            .map(|n| format!("{:^3}", n))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let input = format!(
+            "{}\n{}\n\nmove 1 from 10 to 1\nmove 1 from 9 to 10\n",
+            crates, labels
+        );
+        assert_eq!(top_crates(&input, false, Mode::Part1), "JBCDEFGH I");
+    }
 }
@@ -1,5 +1,4 @@
-use std::io::Read;
-
+use aoclib::days::day6::{run_window, solve_part1, solve_part2};
 use clap::{Parser, ValueEnum};
 
 #[derive(ValueEnum, Debug, PartialEq, Eq, Clone, Copy)]
@@ -11,78 +10,27 @@ enum Mode {
 #[derive(Debug, Parser)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    #[arg(short, long, value_enum)]
-    mode: Mode,
-}
-
-fn is_unique_bytes(s: &[u8], set: &mut bit_set::BitSet) -> bool {
-    for c in s {
-        if !set.insert(*c as usize) {
-            return false;
-        }
-    }
-    true
-}
-
-struct RingyBuf<T: Default + Copy, const N: usize> {
-    storage: [T; N],
-    full: bool,
-    i: usize,
-}
-
-impl<T: Default + Copy, const N: usize> RingyBuf<T, N> {
-    fn new() -> Self {
-        Self {
-            storage: [T::default(); N],
-            full: false,
-            i: 0,
-        }
-    }
-
-    fn push(&mut self, value: T) {
-        self.storage[self.i] = value;
-        self.i = (self.i + 1) % N;
-        if self.i == 0 {
-            self.full = true;
-        }
-    }
-
-    fn is_full(&self) -> bool {
-        self.full
-    }
-
-    fn bytes(&self) -> &[T] {
-        if self.full {
-            &self.storage
-        } else {
-            &self.storage[0..self.i]
-        }
-    }
-}
-
-fn run<const N: usize, R: Read>(io: R) -> Option<usize> {
-    let mut r = RingyBuf::<u8, N>::new();
-    let mut s = bit_set::BitSet::with_capacity(256);
-    for (i, b) in io.bytes().filter_map(Result::ok).enumerate() {
-        r.push(b);
-        if r.is_full() {
-            s.clear();
-            if is_unique_bytes(r.bytes(), &mut s) {
-                return Some(i + 1);
-            }
-        }
-    }
-    None
+    #[arg(short, long, value_enum, required_unless_present = "window")]
+    mode: Option<Mode>,
+    /// Find the first run of this many distinct bytes instead of using a mode's size
+    #[arg(short, long, conflicts_with = "mode", value_parser = clap::value_parser!(u64).range(1..))]
+    window: Option<u64>,
+    #[command(flatten)]
+    common: aoclib::CommonArgs,
 }
 
-fn main() {
+fn main() -> anyhow::Result<()> {
     let args = Args::parse();
-    let stdin = std::io::stdin();
-    let handle = stdin.lock();
-    if let Some(found) = match args.mode {
-        Mode::Part1 => run::<4, _>(handle),
-        Mode::Part2 => run::<14, _>(handle),
-    } {
-        println!("{:?}", found);
-    }
+    let input = args.common.read_input()?;
+    let _timer = args.common.start_timer();
+    let found = match (args.mode, args.window) {
+        (_, Some(window)) => run_window(input.as_bytes(), window as usize)?
+            .ok_or_else(|| anyhow::anyhow!("no run of {} distinct bytes found", window))?
+            .to_string(),
+        (Some(Mode::Part1), None) => solve_part1(&input)?,
+        (Some(Mode::Part2), None) => solve_part2(&input)?,
+        (None, None) => unreachable!("clap requires a mode or a window"),
+    };
+    println!("{}", found);
+    Ok(())
 }
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::io::BufRead;
 
 use clap::{Parser, ValueEnum};
@@ -104,6 +105,26 @@ mod fs {
             self.components.pop();
         }
 
+        /// The last component of the path; empty for the root
+        pub fn name(&self) -> &str {
+            self.components.last().0.as_str()
+        }
+
+        /// How many directories down from the root this is
+        pub fn depth(&self) -> usize {
+            self.components.len() - 1
+        }
+
+        pub fn parent(&self) -> Option<Path> {
+            if self.components.len() == 1 {
+                None
+            } else {
+                let mut parent = self.clone();
+                parent.pop_up();
+                Some(parent)
+            }
+        }
+
         pub fn cd<S: Into<String>>(&mut self, path: S, fs: &Filesystem) -> anyhow::Result<()> {
             let last_block = self.components.last().1;
             let path = path.into();
@@ -303,6 +324,18 @@ mod fs {
                 f(path, item);
             }
         }
+
+        /// Like `walk`, but only visits files, with their sizes
+        pub fn walk_files<F>(&self, mut f: F)
+        where
+            F: FnMut(Path, usize),
+        {
+            self.walk(|path, item| {
+                if let FsItem::INode(inode) = item {
+                    f(path, inode.size)
+                }
+            })
+        }
     }
 }
 
@@ -317,8 +350,19 @@ enum Mode {
 struct Args {
     #[arg(short, long, value_enum)]
     mode: Mode,
+    /// Also print every file and directory with its size, biggest first
+    #[arg(long)]
+    tree: bool,
+    /// Also print the space used and free, and the largest file
+    #[arg(long)]
+    stats: bool,
+    #[command(flatten)]
+    common: aoclib::CommonArgs,
 }
 
+const DISK_SIZE: usize = 70000000;
+const UPDATE_SIZE: usize = 30000000;
+
 #[derive(Debug, PartialEq, Eq)]
 enum Command {
     Ls,
@@ -372,48 +416,221 @@ fn populate_filesystem_from_commands<R: BufRead>(reader: R) -> anyhow::Result<fs
     Ok(fs)
 }
 
+/// Sum the sizes of all directories smaller than 100000
+fn part1(fs: &fs::Filesystem) -> usize {
+    let mut total_size = 0;
+    fs.walk(|_, item| {
+        if item.is_dir() && item.size() < 100000 {
+            total_size += item.size();
+        }
+    });
+    total_size
+}
+
+/// Find the smallest directory which would free up enough space for the update
+fn part2(fs: &fs::Filesystem) -> anyhow::Result<Option<(fs::Path, usize)>> {
+    let mut best_candidate = None;
+    let root_size = fs.get_root_dir().size;
+    if root_size > DISK_SIZE {
+        anyhow::bail!("FS is too big!");
+    }
+    let free = DISK_SIZE - root_size;
+    if free > UPDATE_SIZE {
+        anyhow::bail!("FS already has {}B free", UPDATE_SIZE);
+    }
+    let needed = UPDATE_SIZE - free;
+    fs.walk(|path, item| {
+        if item.is_dir() && item.size() > needed {
+            match best_candidate {
+                None => best_candidate = Some((path, item.size())),
+                Some((_, c)) if c > item.size() => best_candidate = Some((path, item.size())),
+                _ => {}
+            }
+        }
+    });
+    Ok(best_candidate)
+}
+
+#[derive(Debug)]
+struct Stats {
+    used: usize,
+    /// Negative if the filesystem somehow holds more than the disk
+    free: i64,
+    largest_file: Option<(fs::Path, usize)>,
+}
+
+fn stats(fs: &fs::Filesystem) -> Stats {
+    let used = fs.get_root_dir().size;
+    let mut largest_file: Option<(fs::Path, usize)> = None;
+    fs.walk_files(|path, size| {
+        if largest_file
+            .as_ref()
+            .map(|(_, s)| size > *s)
+            .unwrap_or(true)
+        {
+            largest_file = Some((path, size));
+        }
+    });
+    Stats {
+        used,
+        free: DISK_SIZE as i64 - used as i64,
+        largest_file,
+    }
+}
+
+/// Format a size the way `du -h` does: plain bytes, then K/M/G with one decimal below 10
+fn human_size(size: usize) -> String {
+    const UNITS: [&str; 4] = ["K", "M", "G", "T"];
+    if size < 1024 {
+        return size.to_string();
+    }
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if value < 10.0 {
+        format!("{:.1}{}", value, UNITS[unit])
+    } else {
+        format!("{:.0}{}", value, UNITS[unit])
+    }
+}
+
+/// Render the whole filesystem as an indented tree, with each directory's children sorted
+/// biggest first; directories get a trailing `/`
+fn render_tree(fs: &fs::Filesystem) -> String {
+    let mut children: HashMap<String, Vec<(fs::Path, usize, bool)>> = HashMap::new();
+    let mut root = None;
+    fs.walk(|path, item| {
+        let entry = (path.clone(), item.size(), item.is_dir());
+        match path.parent() {
+            Some(parent) => children.entry(parent.to_string()).or_default().push(entry),
+            None => root = Some(entry),
+        }
+    });
+    let mut out = String::new();
+    let mut stack = root.into_iter().collect::<Vec<_>>();
+    while let Some((path, size, is_dir)) = stack.pop() {
+        let name = if path.depth() == 0 { "/" } else { path.name() };
+        out.push_str(&format!(
+            "{:>5}  {}{}{}\n",
+            human_size(size),
+            "  ".repeat(path.depth()),
+            name,
+            if is_dir && path.depth() > 0 { "/" } else { "" }
+        ));
+        if let Some(mut entries) = children.remove(&path.to_string()) {
+            // the stack pops from the end, so smallest goes first; ties go by name
+            entries.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.name().cmp(a.0.name())));
+            stack.extend(entries);
+        }
+    }
+    out
+}
+
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
-    let stdin = std::io::stdin();
-    let mut handle = stdin.lock();
-    let mut fs = populate_filesystem_from_commands(&mut handle)?;
+    let input = args.common.read_input()?;
+    let _timer = args.common.start_timer();
+    let mut fs = populate_filesystem_from_commands(input.as_bytes())?;
     fs.cache_directory_sizes()?;
+    if args.tree {
+        print!("{}", render_tree(&fs));
+    }
+    if args.stats {
+        let stats = stats(&fs);
+        println!("used: {}B", stats.used);
+        println!("free: {}B of {}B", stats.free, DISK_SIZE);
+        if let Some((path, size)) = stats.largest_file {
+            println!("largest file: {} is {}B", path, size);
+        }
+    }
     match args.mode {
         Mode::Part1 => {
-            let mut total_size = 0;
-            fs.walk(|_, item| {
-                if item.is_dir() && item.size() < 100000 {
-                    total_size += item.size();
-                }
-            });
-            println!("total_size = {}", total_size);
+            println!("total_size = {}", part1(&fs));
         }
         Mode::Part2 => {
-            let mut best_candidate = None;
-            let root_size = fs.get_root_dir().size;
-            if root_size > 70000000 {
-                anyhow::bail!("FS is too big!");
-            }
-            let free = 70000000 - root_size;
-            if free > 30000000 {
-                anyhow::bail!("FS already has 30000000B free");
-            }
-            let needed = 30000000 - free;
-            fs.walk(|path, item| {
-                if item.is_dir() && item.size() > needed {
-                    match best_candidate {
-                        None => best_candidate = Some((path, item.size())),
-                        Some((_, c)) if c > item.size() => {
-                            best_candidate = Some((path, item.size()))
-                        }
-                        _ => {}
-                    }
-                }
-            });
-            if let Some((path, size)) = best_candidate {
+            if let Some((path, size)) = part2(&fs)? {
                 println!("{} is {}B", path, size);
             }
         }
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{human_size, part1, part2, populate_filesystem_from_commands, render_tree, stats};
+
+    const SAMPLE: &str = "$ cd /
+$ ls
+dir a
+14848514 b.txt
+8504156 c.dat
+dir d
+$ cd a
+$ ls
+dir e
+29116 f
+2557 g
+62596 h.lst
+$ cd e
+$ ls
+584 i
+$ cd ..
+$ cd ..
+$ cd d
+$ ls
+4060174 j
+8033020 d.log
+5626152 d.ext
+7214296 k
+";
+
+    #[test]
+    fn test_sample() {
+        let mut fs = populate_filesystem_from_commands(SAMPLE.as_bytes()).unwrap();
+        fs.cache_directory_sizes().unwrap();
+        assert_eq!(part1(&fs), 95437);
+        let (path, size) = part2(&fs).unwrap().unwrap();
+        assert_eq!(path.to_string(), "/d");
+        assert_eq!(size, 24933642);
+    }
+
+    #[test]
+    fn test_human_size() {
+        assert_eq!(human_size(584), "584");
+        assert_eq!(human_size(2557), "2.5K");
+        assert_eq!(human_size(94853), "93K");
+        assert_eq!(human_size(8504156), "8.1M");
+        assert_eq!(human_size(48381165), "46M");
+    }
+
+    #[test]
+    fn test_render_tree() {
+        let mut fs = populate_filesystem_from_commands(SAMPLE.as_bytes()).unwrap();
+        fs.cache_directory_sizes().unwrap();
+        let tree = render_tree(&fs);
+        let lines = tree.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 14);
+        assert_eq!(lines[0], "  46M  /");
+        assert_eq!(lines[1], "  24M    d/");
+        assert_eq!(lines[2], " 7.7M      d.log");
+        assert!(lines.contains(&"  93K    a/"));
+        assert_eq!(lines[11], " 2.5K      g");
+        assert_eq!(lines[13], "  584        i");
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut fs = populate_filesystem_from_commands(SAMPLE.as_bytes()).unwrap();
+        fs.cache_directory_sizes().unwrap();
+        let stats = stats(&fs);
+        assert_eq!(stats.used, 48381165);
+        assert_eq!(stats.free, 21618835);
+        let (path, size) = stats.largest_file.unwrap();
+        assert_eq!(path.to_string(), "/b.txt");
+        assert_eq!(size, 14848514);
+    }
+}
@@ -1,82 +1,76 @@
-use std::io::BufRead;
-
+use aoclib::{parse_digit_grid, DenseGrid, Point};
 use clap::{Parser, ValueEnum};
-use nonempty::NonEmpty;
 
 type TreeHeight = u8;
 
 #[derive(Debug)]
 struct Scene {
-    rows: NonEmpty<NonEmpty<TreeHeight>>,
-    cols: NonEmpty<NonEmpty<TreeHeight>>,
+    grid: DenseGrid<TreeHeight>,
 }
 
 impl Scene {
-    fn from_reader<R: BufRead>(r: R) -> anyhow::Result<Self> {
-        let rows = NonEmpty::collect(
-            r.lines()
-                .filter_map(Result::ok)
-                .filter_map(|line| NonEmpty::collect(line.as_bytes().iter().map(|b| b - b'0'))),
-        )
-        .ok_or_else(|| anyhow::anyhow!("no lines found"))?;
-        let cols = NonEmpty::collect(
-            (0..rows.first().len())
-                .filter_map(|i| NonEmpty::collect(rows.iter().map(|row| row[i]))),
-        )
-        .unwrap();
-        Ok(Self { rows, cols })
+    fn parse(s: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            grid: parse_digit_grid(s)?,
+        })
+    }
+
+    fn points(&self) -> impl Iterator<Item = Point> + '_ {
+        (0..self.grid.height() as i64)
+            .flat_map(move |y| (0..self.grid.width() as i64).map(move |x| Point::new(x, y)))
+    }
+
+    /// Whether every tree between this one and some edge is shorter; trees on the edge
+    /// have nothing in the way at all
+    fn is_visible(&self, point: Point) -> bool {
+        let height = self.grid[point];
+        let (x, y) = (point.x as usize, point.y as usize);
+        let row = self.grid.row(point.y);
+        let col = self.grid.column(point.x);
+        row.clone().take(x).all(|i| *i < height)
+            || row.skip(x + 1).all(|i| *i < height)
+            || col.clone().take(y).all(|i| *i < height)
+            || col.skip(y + 1).all(|i| *i < height)
+    }
+
+    /// Which trees can be seen from outside the grid
+    fn visibility(&self) -> DenseGrid<bool> {
+        let mut visible = DenseGrid::new_with(
+            Point::new(0, 0),
+            Point::new(self.grid.width() as i64 - 1, self.grid.height() as i64 - 1),
+            false,
+        );
+        for point in self.points() {
+            visible[point] = self.is_visible(point);
+        }
+        visible
     }
 
     fn num_visible(&self) -> usize {
-        let width = self.rows.first().len() - 1;
-        let height = self.rows.len() - 1;
-        self.rows
-            .iter()
-            .enumerate()
-            .map(|(y, row)| {
-                row.iter()
-                    .enumerate()
-                    .map(|(x, cell)| {
-                        if y == 0 || x == 0 || y == height || x == width {
-                            1
-                        } else {
-                            // check the row
-                            let visible_to_left = row.iter().take(x).all(|i| *i < *cell);
-                            let visible_to_right = row.iter().skip(x + 1).all(|i| *i < *cell);
-                            let visible_above = self.cols[x].iter().take(y).all(|i| *i < *cell);
-                            let visible_below = self.cols[x].iter().skip(y + 1).all(|i| *i < *cell);
-                            usize::from(
-                                visible_to_left
-                                    || visible_to_right
-                                    || visible_above
-                                    || visible_below,
-                            )
-                        }
-                    })
-                    .sum::<usize>()
-            })
-            .sum()
+        let visible = self.visibility();
+        self.points().filter(|p| visible[*p]).count()
+    }
+
+    /// Draw the grid with `#` for trees that can be seen from outside and `.` for the rest
+    fn render_visibility<W: std::io::Write>(&self, w: &mut W) -> std::io::Result<()> {
+        self.visibility()
+            .dump_to(w, |visible| if *visible { '#' } else { '.' })
+    }
+
+    fn scenic_score(&self, point: Point) -> usize {
+        let height = self.grid[point];
+        let (x, y) = (point.x as usize, point.y as usize);
+        let row = self.grid.row(point.y);
+        let col = self.grid.column(point.x);
+        let up_score = scenic_score_helper(col.clone().take(y).rev(), height);
+        let down_score = scenic_score_helper(col.skip(y + 1), height);
+        let left_score = scenic_score_helper(row.clone().take(x).rev(), height);
+        let right_score = scenic_score_helper(row.skip(x + 1), height);
+        left_score * right_score * up_score * down_score
     }
 
     fn max_scenic_score(&self) -> usize {
-        self.rows
-            .iter()
-            .enumerate()
-            .filter_map(|(y, row)| {
-                row.iter()
-                    .enumerate()
-                    .map(|(x, cell)| {
-                        let col = &self.cols[x];
-                        let up_score = scenic_score_helper(col.iter().take(y).rev(), *cell);
-                        let down_score = scenic_score_helper(col.iter().skip(y + 1), *cell);
-                        let left_score = scenic_score_helper(row.iter().take(x).rev(), *cell);
-                        let right_score = scenic_score_helper(row.iter().skip(x + 1), *cell);
-                        left_score * right_score * up_score * down_score
-                    })
-                    .max()
-            })
-            .max()
-            .unwrap()
+        self.points().map(|p| self.scenic_score(p)).max().unwrap()
     }
 }
 
@@ -102,13 +96,21 @@ enum Mode {
 struct Args {
     #[arg(short, long, value_enum)]
     mode: Mode,
+    /// Also draw which trees are visible from outside the grid
+    #[arg(long)]
+    render: bool,
+    #[command(flatten)]
+    common: aoclib::CommonArgs,
 }
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
-    let stdin = std::io::stdin();
-    let mut handle = stdin.lock();
-    let scene = Scene::from_reader(&mut handle)?;
+    let input = args.common.read_input()?;
+    let _timer = args.common.start_timer();
+    let scene = Scene::parse(&input)?;
+    if args.render {
+        scene.render_visibility(&mut std::io::stdout().lock())?;
+    }
     if args.mode == Mode::Part1 {
         println!("{}", scene.num_visible());
     } else {
@@ -116,3 +118,52 @@ fn main() -> anyhow::Result<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Scene;
+
+    const SAMPLE: &str = "30373
+25512
+65332
+33549
+35390
+";
+
+    #[test]
+    fn test_sample() {
+        let scene = Scene::parse(SAMPLE).unwrap();
+        assert_eq!(scene.num_visible(), 21);
+        assert_eq!(scene.max_scenic_score(), 8);
+    }
+
+    #[test]
+    fn test_render_visibility() {
+        let scene = Scene::parse(SAMPLE).unwrap();
+        let mut out = Vec::new();
+        scene.render_visibility(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out, "#####\n###.#\n##.##\n#.#.#\n#####\n");
+        assert_eq!(out.matches('#').count(), 21);
+    }
+
+    #[test]
+    fn test_hand_written_grids() {
+        // (grid, visible trees, best scenic score), as the nested rows-and-columns version
+        // worked them out
+        let grids = [
+            ("9\n", 1, 0),
+            ("1234\n", 4, 0),
+            ("1\n2\n3\n", 3, 0),
+            // only the rim is visible; each 5 sees 2 up, down, and out, and 1 towards the
+            // other 5
+            ("999999\n911119\n915519\n911119\n999999\n", 18, 8),
+            ("0123\n7894\n6105\n5432\n1989\n", 18, 6),
+        ];
+        for (grid, visible, score) in grids {
+            let scene = Scene::parse(grid).unwrap();
+            assert_eq!(scene.num_visible(), visible, "{}", grid);
+            assert_eq!(scene.max_scenic_score(), score, "{}", grid);
+        }
+    }
+}
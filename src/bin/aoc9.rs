@@ -1,9 +1,10 @@
-use std::collections::HashSet;
+use std::collections::HashMap;
 use std::io::BufRead;
 use std::str::FromStr;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
+use aoclib::{DenseGrid, Point};
 use clap::Parser;
 use crossterm::{
     cursor::{Hide, MoveTo, Show},
@@ -101,14 +102,16 @@ impl FromStr for Command {
 struct Knot {
     label: char,
     position: Coordinate,
-    visited_positions: HashSet<Coordinate>,
+    /// How many steps ended with this knot at each position; the starting position is
+    /// included with a count of zero
+    visited_positions: HashMap<Coordinate, u32>,
 }
 
 impl Knot {
     fn new(label: u8) -> Self {
         let position = Coordinate::default();
-        let mut visited_positions = HashSet::new();
-        visited_positions.insert(position);
+        let mut visited_positions = HashMap::new();
+        visited_positions.insert(position, 0);
         let label = if label == 0 {
             'H'
         } else {
@@ -122,7 +125,7 @@ impl Knot {
     }
 
     fn move_to(&mut self, position: Coordinate) {
-        self.visited_positions.insert(position);
+        *self.visited_positions.entry(position).or_default() += 1;
         self.position = position;
     }
 
@@ -150,6 +153,8 @@ struct Args {
     ms_per_frame: u64,
     #[arg(long)]
     trails: bool,
+    #[command(flatten)]
+    common: aoclib::CommonArgs,
 }
 
 fn render<W: std::io::Write>(
@@ -174,7 +179,7 @@ fn render<W: std::io::Write>(
                     && knots
                         .iter()
                         .last()
-                        .map(|k| k.visited_positions.contains(&coord))
+                        .map(|k| k.visited_positions.contains_key(&coord))
                         .unwrap_or(false)
                 {
                     '#'
@@ -190,10 +195,43 @@ fn render<W: std::io::Write>(
     Ok(())
 }
 
+/// Move the head one step and have every other knot follow it
+fn step(knots: &mut [Knot], ordinal: Ordinal) {
+    knots[0].apply(ordinal);
+    for knot_offset in 1..knots.len() {
+        let dir = knots[knot_offset].follow(&knots[knot_offset - 1]);
+        knots[knot_offset].move_to(dir);
+    }
+}
+
+fn coordinate_to_point(coordinate: &Coordinate) -> Point {
+    // y increases going up, but grids are drawn top-down
+    Point::new(coordinate.x as i64, -coordinate.y as i64)
+}
+
+/// Build a grid counting how many steps ended with this knot in each cell
+fn heatmap(knot: &Knot) -> DenseGrid<u32> {
+    // every visited cell counts once towards the bounds, including the start (which has a
+    // count of 0), and then gets its real count
+    let mut grid = DenseGrid::from_points(knot.visited_positions.keys().map(coordinate_to_point));
+    for (coordinate, count) in knot.visited_positions.iter() {
+        grid[coordinate_to_point(coordinate)] = *count;
+    }
+    grid
+}
+
+fn heat_char(count: &u32) -> char {
+    match count {
+        0 => '.',
+        1..=9 => char::from_digit(*count, 10).unwrap(),
+        _ => '+',
+    }
+}
+
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
-    let stdin_r = std::io::stdin();
-    let stdin = stdin_r.lock();
+    let input = args.common.read_input()?;
+    let _timer = args.common.start_timer();
     let num_knots = args.num_knots;
     let mut knots = (0..num_knots).map(Knot::new).collect::<Vec<Knot>>();
     let stdout_r = std::io::stdout();
@@ -211,14 +249,10 @@ fn main() -> anyhow::Result<()> {
         execute!(&mut stdout, Hide)?;
     }
     let mut applied = 0u64;
-    for (i, line) in stdin.lines().enumerate() {
+    for (i, line) in input.as_bytes().lines().enumerate() {
         let command: Command = line?.parse()?;
         for _ in 0..command.step {
-            knots[0].apply(command.ordinal);
-            for knot_offset in 1..knots.len() {
-                let dir = knots[knot_offset].follow(&knots[knot_offset - 1]);
-                knots.get_mut(knot_offset).unwrap().move_to(dir);
-            }
+            step(&mut knots, command.ordinal);
             applied += 1;
             if args.verbose {
                 render(&mut stdout, knots.as_slice(), applied, i, args.trails)?;
@@ -234,7 +268,85 @@ fn main() -> anyhow::Result<()> {
         execute!(&mut stdout, LeaveAlternateScreen)?;
     }
     if let Some(last) = knots.last() {
+        if args.verbose {
+            heatmap(last).dump_with(heat_char);
+        }
         println!("{}", last.visited_positions.len());
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{coordinate_to_point, heatmap, step, Command, Knot};
+
+    const SAMPLE: &str = "R 4
+U 4
+L 3
+D 1
+R 4
+D 1
+L 5
+R 2
+";
+
+    #[test]
+    fn test_heatmap_sums_to_steps() {
+        let mut knots = (0..2).map(Knot::new).collect::<Vec<Knot>>();
+        let mut total_steps = 0;
+        for line in SAMPLE.lines() {
+            let command: Command = line.parse().unwrap();
+            for _ in 0..command.step {
+                step(&mut knots, command.ordinal);
+                total_steps += 1;
+            }
+        }
+        let tail = knots.last().unwrap();
+        assert_eq!(tail.visited_positions.len(), 13);
+        let grid = heatmap(tail);
+        let summed: u32 = tail
+            .visited_positions
+            .keys()
+            .map(|c| grid[coordinate_to_point(c)])
+            .sum();
+        assert_eq!(summed, total_steps);
+    }
+
+    const LARGER_SAMPLE: &str = "R 5
+U 8
+L 8
+D 3
+R 17
+D 10
+L 25
+U 20
+";
+
+    fn tail_positions(input: &str, num_knots: u8) -> usize {
+        let mut knots = (0..num_knots).map(Knot::new).collect::<Vec<Knot>>();
+        for line in input.lines() {
+            let command: Command = line.parse().unwrap();
+            for _ in 0..command.step {
+                step(&mut knots, command.ordinal);
+            }
+        }
+        knots.last().unwrap().visited_positions.len()
+    }
+
+    #[test]
+    fn test_tail_knots() {
+        assert_eq!(tail_positions(SAMPLE, 2), 13);
+        assert_eq!(tail_positions(SAMPLE, 10), 1);
+        assert_eq!(tail_positions(LARGER_SAMPLE, 10), 36);
+    }
+
+    #[test]
+    fn test_labels_for_every_knot_count() {
+        assert_eq!(Knot::new(0).label, 'H');
+        for label in 1..=u8::MAX {
+            let knot = Knot::new(label);
+            assert!(knot.label.is_ascii_graphic(), "{:?}", knot.label);
+            assert_ne!(knot.label, 'H');
+        }
+    }
+}
@@ -0,0 +1,46 @@
+//! The `aoc` binary should route to each day's library solver
+
+use std::process::{Command, Output};
+
+fn run_aoc(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_aoc"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_dispatches_to_days() {
+    let cases = [
+        (&["1", "1", "--input", "inputs/1"][..], "24000\n"),
+        (&["1", "2", "--input", "inputs/1"][..], "45000\n"),
+        (&["20", "2", "--input", "inputs/20"][..], "1623178306\n"),
+        (&["25", "1", "--input", "inputs/25"][..], "2=-1=0\n"),
+    ];
+    for (args, expected) in cases {
+        let output = run_aoc(args);
+        assert!(output.status.success(), "{:?}: {:?}", args, output);
+        assert!(output.stderr.is_empty(), "{:?}: {:?}", args, output);
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+    }
+}
+
+#[test]
+fn test_time() {
+    let output = run_aoc(&["6", "2", "--input", "inputs/6", "--time"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "19\n");
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .starts_with("solved in "));
+}
+
+#[test]
+fn test_unavailable_day() {
+    let output = run_aoc(&["2", "1", "--input", "inputs/2"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("day 2 isn't available"));
+    assert!(!run_aoc(&["1", "3", "--input", "inputs/1"]).status.success());
+}
@@ -0,0 +1,66 @@
+//! `--quiet` should leave nothing on stdout but the answer, so it can be piped elsewhere
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn run_quiet(exe: &str, input: &str, args: &[&str]) -> String {
+    let mut child = Command::new(exe)
+        .args(args)
+        .arg("--quiet")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(std::fs::read_to_string(input).unwrap().as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert!(output.stderr.is_empty(), "{:?}", output);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_quiet_prints_only_the_answer() {
+    let cases = [
+        (
+            env!("CARGO_BIN_EXE_aoc15"),
+            "inputs/15",
+            &["-m", "part1", "-p", "10"][..],
+            "26\n",
+        ),
+        (
+            env!("CARGO_BIN_EXE_aoc15"),
+            "inputs/15",
+            &["-m", "part2", "-p", "20"][..],
+            "56000011\n",
+        ),
+        (
+            env!("CARGO_BIN_EXE_aoc16"),
+            "inputs/16",
+            &["-m", "part1"][..],
+            "1651\n",
+        ),
+        (
+            env!("CARGO_BIN_EXE_aoc21"),
+            "inputs/21",
+            &["-m", "part1"][..],
+            "152\n",
+        ),
+        (
+            env!("CARGO_BIN_EXE_aoc24"),
+            "inputs/24",
+            &["-m", "part2"][..],
+            "54\n",
+        ),
+    ];
+    for (exe, input, args, expected) in cases {
+        let output = run_quiet(exe, input, args);
+        assert_eq!(output.lines().count(), 1, "{}: {:?}", exe, output);
+        assert_eq!(output, expected, "{}", exe);
+    }
+}
@@ -0,0 +1,32 @@
+//! The library solvers should give the known answers for each day's sample input
+
+use aoclib::days::{day1, day20, day25, day6};
+
+fn sample(day: u32) -> String {
+    std::fs::read_to_string(format!("inputs/{}", day)).unwrap()
+}
+
+#[test]
+fn test_day1() {
+    assert_eq!(day1::solve_part1(&sample(1)).unwrap(), "24000");
+    assert_eq!(day1::solve_part2(&sample(1)).unwrap(), "45000");
+}
+
+#[test]
+fn test_day6() {
+    assert_eq!(day6::solve_part1(&sample(6)).unwrap(), "7");
+    assert_eq!(day6::solve_part2(&sample(6)).unwrap(), "19");
+    assert!(day6::solve_part1("aaaa").is_err());
+}
+
+#[test]
+fn test_day20() {
+    assert_eq!(day20::solve_part1(&sample(20)).unwrap(), "3");
+    assert_eq!(day20::solve_part2(&sample(20)).unwrap(), "1623178306");
+}
+
+#[test]
+fn test_day25() {
+    assert_eq!(day25::solve_part1(&sample(25)).unwrap(), "2=-1=0");
+    assert!(day25::solve_part2(&sample(25)).is_err());
+}
@@ -0,0 +1,36 @@
+//! `--time` should add the solve time on stderr without changing the answer on stdout
+
+use std::process::{Command, Output};
+
+fn run(exe: &str, args: &[&str]) -> Output {
+    let output = Command::new(exe).args(args).output().unwrap();
+    assert!(output.status.success(), "{}: {:?}", exe, output);
+    output
+}
+
+#[test]
+fn test_time_does_not_change_answer() {
+    let cases = [
+        (
+            env!("CARGO_BIN_EXE_aoc18"),
+            &["-m", "part2", "--input", "inputs/18"][..],
+        ),
+        (
+            env!("CARGO_BIN_EXE_aoc21"),
+            &["-m", "part1", "--quiet", "--input", "inputs/21"][..],
+        ),
+        (
+            env!("CARGO_BIN_EXE_aoc24"),
+            &["-m", "part1", "--quiet", "--input", "inputs/24"][..],
+        ),
+    ];
+    for (exe, args) in cases {
+        let plain = run(exe, args);
+        let timed = run(exe, &[args, &["--time"]].concat());
+        assert_eq!(timed.stdout, plain.stdout, "{}", exe);
+        assert!(plain.stderr.is_empty(), "{}: {:?}", exe, plain);
+        let stderr = String::from_utf8(timed.stderr).unwrap();
+        assert_eq!(stderr.lines().count(), 1, "{}: {:?}", exe, stderr);
+        assert!(stderr.starts_with("solved in "), "{}: {:?}", exe, stderr);
+    }
+}
@@ -0,0 +1,106 @@
+//! Solve times on large inputs, for comparing before and after a performance change.
+//!
+//! These are ignored by default since they only mean anything in a release build:
+//!
+//! ```text
+//! cargo test --release --test timing -- --ignored --nocapture
+//! ```
+//!
+//! Only the samples are checked in, so the inputs are generated to roughly the size of the
+//! real ones where the sample format allows it.
+
+use std::process::Command;
+
+/// A small deterministic generator, so every run times the same input
+fn pseudo_random(count: usize, seed: u64) -> impl Iterator<Item = u64> {
+    let mut state = seed;
+    (0..count).map(move |_| {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        state >> 33
+    })
+}
+
+/// Run `exe` with `--time` on `input`, returning its `solved in ...` line
+fn time(exe: &str, name: &str, input: &str, args: &[&str]) -> String {
+    let path = std::env::temp_dir().join(format!("aoc2022-timing-{}", name));
+    std::fs::write(&path, input).unwrap();
+    let output = Command::new(exe)
+        .args(args)
+        .arg("--time")
+        .arg("--input")
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(output.status.success(), "{}: {:?}", name, output);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    stderr
+        .lines()
+        .find(|l| l.starts_with("solved in "))
+        .unwrap_or_else(|| panic!("{}: no time in {:?}", name, stderr))
+        .to_string()
+}
+
+#[test]
+#[ignore]
+fn time_large_inputs() {
+    let sample = |day: u32| std::fs::read_to_string(format!("inputs/{}", day)).unwrap();
+
+    // 300,000 rucksacks; the sample is two whole groups, so it repeats cleanly
+    let rucksacks = sample(3).repeat(50_000);
+    // a megabyte with no marker until the very end
+    let mut signal = "abcabcabd".repeat(1_000_000 / 9);
+    signal.push_str("efghijklmnopqrstuvwxyz");
+    // 5,000 numbers like the real file, with exactly one 0
+    let mut numbers = pseudo_random(4999, 20)
+        .map(|r| (r % 20001) as i64 - 10000)
+        .map(|n| if n == 0 { 1 } else { n })
+        .map(|n| n.to_string())
+        .collect::<Vec<_>>();
+    numbers.insert(2500, "0".to_string());
+    let numbers = numbers.join("\n") + "\n";
+
+    let cases = [
+        (
+            env!("CARGO_BIN_EXE_aoc3"),
+            "aoc3-part1",
+            &rucksacks,
+            &["-m", "part1"][..],
+        ),
+        (
+            env!("CARGO_BIN_EXE_aoc3"),
+            "aoc3-part2",
+            &rucksacks,
+            &["-m", "part2"][..],
+        ),
+        (
+            env!("CARGO_BIN_EXE_aoc6"),
+            "aoc6-window",
+            &signal,
+            &["-w", "14"][..],
+        ),
+        (
+            env!("CARGO_BIN_EXE_aoc20"),
+            "aoc20-part2",
+            &numbers,
+            &["-m", "part2"][..],
+        ),
+        (
+            env!("CARGO_BIN_EXE_aoc16"),
+            "aoc16-part2",
+            &sample(16),
+            &["-m", "part2"][..],
+        ),
+        (
+            env!("CARGO_BIN_EXE_aoc24"),
+            "aoc24-part2",
+            &sample(24),
+            &["-m", "part2"][..],
+        ),
+    ];
+    for (exe, name, input, args) in cases {
+        println!("{}: {}", name, time(exe, name, input, args));
+    }
+}